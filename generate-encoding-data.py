@@ -12,6 +12,7 @@
 import json
 import subprocess
 import sys
+import unicodedata
 
 def cmp_from_end(one, other):
   c = cmp(len(one), len(other))
@@ -38,7 +39,7 @@ class CodePage:
     self.code_page = code_page
     self.preferred = preferred
   def __cmp__(self, other):
-    return self.code_page, other.code_page
+    return cmp(self.code_page, other.code_page)
 
 def static_u16_table(name, data):
   data_file.write('''pub static %s: [u16; %d] = [
@@ -159,14 +160,98 @@ encoding_by_alias_code_page = {
   20949: "EUC-KR",
   28591: "windows-1252",
   28599: "windows-1254",
-  28601: "windows-847",
+  28601: "windows-874",
   50220: "ISO-2022-JP",
   50222: "ISO-2022-JP",
-  51949: "EUC-JP",
+  51932: "EUC-JP",
   51936: "GBK",
   51949: "EUC-KR",
 }
 
+for code_page, encoding in encodings_by_code_page.iteritems():
+  code_pages.append(CodePage(code_page, encoding))
+
+for code_page, encoding in encoding_by_alias_code_page.iteritems():
+  code_pages.append(CodePage(code_page, encoding))
+
+code_pages.sort()
+
+# IANA charset MIBenums and the charset name each one is registered for.
+# The names are resolved to encodings as labels.
+labels_by_mib_enum = {
+  3: "us-ascii",
+  4: "iso-8859-1",
+  5: "iso-8859-2",
+  6: "iso-8859-3",
+  7: "iso-8859-4",
+  8: "iso-8859-5",
+  9: "iso-8859-6",
+  10: "iso-8859-7",
+  11: "iso-8859-8",
+  12: "iso-8859-9",
+  13: "iso-8859-10",
+  17: "shift_jis",
+  18: "euc-jp",
+  36: "ks_c_5601-1987",
+  37: "iso-2022-kr",
+  38: "euc-kr",
+  39: "iso-2022-jp",
+  57: "gb_2312-80",
+  81: "iso-8859-6-e",
+  82: "iso-8859-6-i",
+  84: "iso-8859-8-e",
+  85: "iso-8859-8-i",
+  104: "iso-2022-cn",
+  105: "iso-2022-cn-ext",
+  106: "utf-8",
+  109: "iso-8859-13",
+  110: "iso-8859-14",
+  111: "iso-8859-15",
+  112: "iso-8859-16",
+  113: "gbk",
+  114: "gb18030",
+  1013: "utf-16be",
+  1014: "utf-16le",
+  1015: "utf-16",
+  2024: "windows-31j",
+  2025: "gb2312",
+  2026: "big5",
+  2027: "macintosh",
+  2084: "koi8-r",
+  2085: "hz-gb-2312",
+  2086: "ibm866",
+  2088: "koi8-u",
+  2101: "big5-hkscs",
+  2109: "windows-874",
+  2250: "windows-1250",
+  2251: "windows-1251",
+  2252: "windows-1252",
+  2253: "windows-1253",
+  2254: "windows-1254",
+  2255: "windows-1255",
+  2256: "windows-1256",
+  2257: "windows-1257",
+  2258: "windows-1258",
+  2259: "tis-620",
+}
+
+def encoding_for_label(label):
+  for candidate in labels:
+    if candidate.label == label:
+      return candidate.preferred
+  print "No encoding has the label %s. Exiting." % label
+  sys.exit(-1)
+
+mib_enums = []
+
+mib_enums_by_encoding = {}
+
+for mib_enum in sorted(labels_by_mib_enum.keys()):
+  encoding = encoding_for_label(labels_by_mib_enum[mib_enum])
+  mib_enums.append((mib_enum, encoding))
+  if labels_by_mib_enum[mib_enum] == encoding.lower():
+    mib_enums_by_encoding[encoding] = mib_enum
+
 #
 
 for group in data:
@@ -264,6 +349,7 @@ for name in preferred:
 /// items.
 pub static %s_INIT: Encoding = Encoding {
     name: "%s",
+    id: EncodingId::%s,
     variant: VariantEncoding::%s,
 };
 
@@ -276,7 +362,7 @@ pub static %s_INIT: Encoding = Encoding {
 /// `static`.
 pub static %s: &'static Encoding = &%s_INIT;
 
-''' % (to_dom_name(name), to_constant_name(name), to_constant_name(name), to_dom_name(name), variant, to_dom_name(name), doctext, to_constant_name(name), to_constant_name(name)))
+''' % (to_dom_name(name), to_constant_name(name), to_constant_name(name), to_dom_name(name), to_camel_name(name.lower()), variant, to_dom_name(name), doctext, to_constant_name(name), to_constant_name(name)))
 
 label_file.write("""static LABELS_SORTED: [&'static str; %d] = [
 """ % len(labels))
@@ -294,6 +380,84 @@ for label in labels:
 
 label_file.write('''];
 
+static ENCODINGS: [&Encoding; %d] = [
+''' % len(preferred))
+
+for name in preferred:
+  label_file.write('''&%s_INIT,\n''' % to_constant_name(name))
+
+label_file.write('''];
+
+static ENCODING_IDS: [EncodingId; %d] = [
+''' % len(preferred))
+
+for name in preferred:
+  label_file.write('''EncodingId::%s,\n''' % to_camel_name(name.lower()))
+
+label_file.write('''];
+
+/// A compact identifier of an encoding, for storing in packed records,
+/// bit sets or FFI structs.
+///
+/// The discriminants follow the order of `Encoding::all()`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EncodingId {
+''')
+
+for i in xrange(len(preferred)):
+  label_file.write('''/// [%s](static.%s.html)\n%s = %d,\n''' % (to_dom_name(preferred[i]), to_constant_name(preferred[i]), to_camel_name(preferred[i].lower()), i))
+
+label_file.write('''}
+
+static MIB_ENUMS_SORTED: [u16; %d] = [
+''' % len(mib_enums))
+
+for (mib_enum, encoding) in mib_enums:
+  label_file.write('''%d,\n''' % mib_enum)
+
+label_file.write('''];
+
+static ENCODINGS_IN_MIB_ENUM_SORT: [&Encoding; %d] = [
+''' % len(mib_enums))
+
+for (mib_enum, encoding) in mib_enums:
+  label_file.write('''&%s_INIT,\n''' % to_constant_name(encoding))
+
+label_file.write('''];
+
+static MIB_ENUMS_BY_ENCODING: [(&Encoding, u16); %d] = [
+''' % len(mib_enums_by_encoding))
+
+for encoding in sorted(mib_enums_by_encoding.keys()):
+  label_file.write('''(&%s_INIT, %d),\n''' % (to_constant_name(encoding), mib_enums_by_encoding[encoding]))
+
+label_file.write('''];
+
+static CODE_PAGES_SORTED: [u16; %d] = [
+''' % len(code_pages))
+
+for code_page in code_pages:
+  label_file.write('''%d,\n''' % code_page.code_page)
+
+label_file.write('''];
+
+static ENCODINGS_IN_CODE_PAGE_SORT: [&Encoding; %d] = [
+''' % len(code_pages))
+
+for code_page in code_pages:
+  label_file.write('''&%s_INIT,\n''' % to_constant_name(code_page.preferred))
+
+label_file.write('''];
+
+static CODE_PAGES_BY_ENCODING: [(&Encoding, u16); %d] = [
+''' % len(code_pages_by_encoding))
+
+for encoding in sorted(code_pages_by_encoding.keys()):
+  label_file.write('''(&%s_INIT, %d),\n''' % (to_constant_name(encoding), code_pages_by_encoding[encoding]))
+
+label_file.write('''];
+
 ''')
 label_file.write(lib_rs_end)
 label_file.close()
@@ -930,6 +1094,189 @@ level1_hanzi_pairs.sort(key=lambda x: x[0])
 static_u16_table_from_indexable("GB2312_LEVEL1_HANZI_CODE_POINTS", level1_hanzi_pairs, 0, "less-slow-gb-hanzi-encode")
 static_u8_pair_table_from_indexable("GB2312_LEVEL1_HANZI_BYTES", level1_hanzi_pairs, 1, "less-slow-gb-hanzi-encode")
 
+# Best-fit fallback for unmappable characters
+
+# Approximations that compatibility decomposition doesn't yield. The
+# decompositions cover accented Latin letters, full-width and other
+# compatibility forms.
+best_fit_extra = {
+  0x00A1: u"!",
+  0x00A2: u"c",
+  0x00A3: u"L",
+  0x00A4: u"$",
+  0x00A5: u"Y",
+  0x00A6: u"|",
+  0x00A7: u"S",
+  0x00A8: u"\"",
+  0x00A9: u"(C)",
+  0x00AB: u"<<",
+  0x00AC: u"-",
+  0x00AD: u"-",
+  0x00AE: u"(R)",
+  0x00AF: u"-",
+  0x00B0: u"o",
+  0x00B1: u"+/-",
+  0x00B4: u"'",
+  0x00B5: u"u",
+  0x00B6: u"P",
+  0x00B7: u".",
+  0x00B8: u",",
+  0x00BB: u">>",
+  0x00BC: u"1/4",
+  0x00BD: u"1/2",
+  0x00BE: u"3/4",
+  0x00BF: u"?",
+  0x00C6: u"AE",
+  0x00D0: u"D",
+  0x00D7: u"x",
+  0x00D8: u"O",
+  0x00DE: u"TH",
+  0x00DF: u"ss",
+  0x00E6: u"ae",
+  0x00F0: u"d",
+  0x00F7: u"/",
+  0x00F8: u"o",
+  0x00FE: u"th",
+  0x0110: u"D",
+  0x0111: u"d",
+  0x0126: u"H",
+  0x0127: u"h",
+  0x0131: u"i",
+  0x0141: u"L",
+  0x0142: u"l",
+  0x0152: u"OE",
+  0x0153: u"oe",
+  0x0166: u"T",
+  0x0167: u"t",
+  0x0180: u"b",
+  0x0192: u"f",
+  0x02C6: u"^",
+  0x02C7: u"^",
+  0x02C8: u"'",
+  0x02CB: u"`",
+  0x02CD: u"_",
+  0x02DC: u"~",
+  0x2010: u"-",
+  0x2011: u"-",
+  0x2012: u"-",
+  0x2013: u"-",
+  0x2014: u"-",
+  0x2015: u"-",
+  0x2016: u"||",
+  0x2017: u"_",
+  0x2018: u"'",
+  0x2019: u"'",
+  0x201A: u",",
+  0x201B: u"'",
+  0x201C: u"\"",
+  0x201D: u"\"",
+  0x201E: u"\"",
+  0x201F: u"\"",
+  0x2020: u"+",
+  0x2022: u"*",
+  0x2030: u"%",
+  0x2032: u"'",
+  0x2033: u"\"",
+  0x2035: u"`",
+  0x2039: u"<",
+  0x203A: u">",
+  0x2044: u"/",
+  0x20AC: u"EUR",
+  0x2190: u"<-",
+  0x2192: u"->",
+  0x2194: u"<->",
+  0x21D0: u"<=",
+  0x21D2: u"=>",
+  0x21D4: u"<=>",
+  0x2212: u"-",
+  0x2215: u"/",
+  0x2216: u"\\",
+  0x2217: u"*",
+  0x2219: u".",
+  0x221E: u"oo",
+  0x2223: u"|",
+  0x2248: u"~",
+  0x2260: u"!=",
+  0x2264: u"<=",
+  0x2265: u">=",
+  0x226A: u"<<",
+  0x226B: u">>",
+  0x2302: u"^",
+  0x2500: u"-",
+  0x2502: u"|",
+  0x250C: u"+",
+  0x2510: u"+",
+  0x2514: u"+",
+  0x2518: u"+",
+  0x251C: u"+",
+  0x2524: u"+",
+  0x252C: u"+",
+  0x2534: u"+",
+  0x253C: u"+",
+  0x2550: u"=",
+  0x2551: u"|",
+  0x25CB: u"o",
+  0x25CF: u"*",
+  0x3001: u",",
+  0x3002: u".",
+  0x3008: u"<",
+  0x3009: u">",
+  0x300A: u"<<",
+  0x300B: u">>",
+  0x300C: u"\"",
+  0x300D: u"\"",
+  0x3010: u"[",
+  0x3011: u"]",
+  0x301C: u"~",
+  0x30FB: u".",
+  0x30FC: u"-",
+  0xFF5F: u"((",
+  0xFF60: u"))",
+  0xFF61: u".",
+  0xFF62: u"\"",
+  0xFF63: u"\"",
+  0xFF64: u",",
+  0xFF65: u".",
+  0xFF70: u"-",
+}
+
+def best_fit_for(code_point):
+  if code_point in best_fit_extra:
+    return best_fit_extra[code_point]
+  c = unichr(code_point)
+  if unicodedata.category(c) in ("Cs", "Co", "Cn"):
+    return None
+  decomposition = unicodedata.normalize("NFKD", c)
+  approximation = u"".join([d for d in decomposition if not unicodedata.combining(d)])
+  if approximation == u"" or approximation == c:
+    return None
+  for d in approximation:
+    if ord(d) < 0x20 or ord(d) > 0x7E:
+      return None
+  if approximation.isspace() and unicodedata.category(c) != "Zs":
+    return None
+  return approximation
+
+best_fit = []
+for code_point in xrange(0x80, 0x10000):
+  approximation = best_fit_for(code_point)
+  if approximation:
+    best_fit.append((code_point, approximation))
+
+static_u16_table("BEST_FIT_CODE_POINTS", [pair[0] for pair in best_fit])
+
+data_file.write('''pub static BEST_FIT_REPLACEMENTS: [&str; %d] = [
+''' % len(best_fit))
+
+for pair in best_fit:
+  data_file.write('"%s",\n' % pair[1].replace('\\', '\\\\').replace('"', '\\"'))
+
+data_file.write('''];
+
+pub const BEST_FIT_LONGEST_REPLACEMENT: usize = %d;
+
+''' % max([len(pair[1]) for pair in best_fit]))
+
 data_file.write('''#[inline(always)]
 fn map_with_ranges(haystack: &[u16], other: &[u16], needle: u16) -> u16 {
     debug_assert_eq!(haystack.len(), other.len());
@@ -1422,6 +1769,14 @@ pub fn big5_other_encode(bmp: u16) -> Option<usize> {
 pub fn mul_94(lead: u8) -> usize {
     lead as usize * 94
 }
+
+#[inline(always)]
+pub fn best_fit(bmp: u16) -> Option<&'static str> {
+    match BEST_FIT_CODE_POINTS.binary_search(&bmp) {
+        Ok(i) => Some(BEST_FIT_REPLACEMENTS[i]),
+        Err(_) => None,
+    }
+}
 ''')
 
 data_file.close()
@@ -1556,6 +1911,8 @@ write_variant_method("max_buffer_length_from_utf16_without_replacement", False,
 
 write_variant_method("max_buffer_length_from_utf8_without_replacement", False, [("byte_length", "usize")], "Option<usize>", encoder_variants, [], "Encoder")
 
+write_variant_method("is_mappable", False, [("c", "char")], "bool", encoder_variants, [], "Encoder")
+
 write_variant_method("encode_from_utf16_raw", True, [("src", "&[u16]"),
                            ("dst", "&mut [u8]"),
                            ("last", "bool")], "(EncoderResult, usize, usize)", encoder_variants, [], "Encoder")
@@ -0,0 +1,189 @@
+// Copyright 2015-2016 Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encoding determination for CSS stylesheets.
+//!
+//! This module implements the [_determine the fallback encoding_][1] steps
+//! of CSS Syntax Level 3 together with the BOM sniffing that the [_decode_][2]
+//! algorithm of the Encoding Standard performs before the fallback encoding
+//! is used.
+//!
+//! [1]: https://drafts.csswg.org/css-syntax/#determine-the-fallback-encoding
+//! [2]: https://encoding.spec.whatwg.org/#decode
+
+use super::Encoding;
+use super::UTF_16BE;
+use super::UTF_16LE;
+use super::UTF_8;
+
+/// The number of bytes from the start of the stylesheet within which the
+/// `@charset` rule has to end.
+const CHARSET_RULE_LIMIT: usize = 1024;
+
+/// What determined the encoding of a stylesheet.
+///
+/// Available to Rust only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CssEncodingSource {
+    /// A byte order mark.
+    Bom,
+    /// The protocol, e.g. the `charset` parameter of the HTTP `Content-Type`.
+    Protocol,
+    /// An `@charset` rule at the very start of the stylesheet.
+    CharsetRule,
+    /// The environment encoding, i.e. the encoding of the referring document.
+    Environment,
+    /// Nothing; CSS defaults to UTF-8.
+    Default,
+}
+
+/// Determines the encoding of a stylesheet.
+///
+/// `bytes` is the start of the stylesheet; an `@charset` rule is only
+/// recognized if it ends within the first 1024 bytes. `protocol_label` is
+/// the encoding label supplied by the protocol, if any, and `environment`
+/// is the environment encoding, if any.
+///
+/// The steps are tried in the order the specifications give: a BOM, a
+/// recognized protocol label, an `@charset` rule that matches the exact byte
+/// pattern `@charset "<label>";`, the environment encoding and finally
+/// UTF-8. As CSS requires, an `@charset` rule naming UTF-16BE or UTF-16LE
+/// yields UTF-8, since a rule that can be read as ASCII cannot be in UTF-16.
+///
+/// The BOM, if any, is removed by the decoder returned by
+/// [`Encoding::new_decoder()`][1] on the returned encoding.
+///
+/// Available to Rust only.
+///
+/// [1]: ../struct.Encoding.html#method.new_decoder
+pub fn determine_encoding(
+    bytes: &[u8],
+    protocol_label: Option<&[u8]>,
+    environment: Option<&'static Encoding>,
+) -> (&'static Encoding, CssEncodingSource) {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        return (encoding, CssEncodingSource::Bom);
+    }
+    if let Some(encoding) = protocol_label.and_then(Encoding::for_label) {
+        return (encoding, CssEncodingSource::Protocol);
+    }
+    if let Some(encoding) = charset_rule(bytes) {
+        if encoding == UTF_16BE || encoding == UTF_16LE {
+            return (UTF_8, CssEncodingSource::CharsetRule);
+        }
+        return (encoding, CssEncodingSource::CharsetRule);
+    }
+    if let Some(encoding) = environment {
+        return (encoding, CssEncodingSource::Environment);
+    }
+    (UTF_8, CssEncodingSource::Default)
+}
+
+/// Resolves the label of an `@charset "...";` rule at the start of `bytes`.
+fn charset_rule(bytes: &[u8]) -> Option<&'static Encoding> {
+    let bytes = if bytes.len() > CHARSET_RULE_LIMIT {
+        &bytes[..CHARSET_RULE_LIMIT]
+    } else {
+        bytes
+    };
+    let prefix = b"@charset \"";
+    if !bytes.starts_with(prefix) {
+        return None;
+    }
+    let rest = &bytes[prefix.len()..];
+    let len = rest.iter().position(|&b| b == b'"')?;
+    if rest.get(len + 1) != Some(&b';') {
+        return None;
+    }
+    Encoding::for_label(&rest[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    #[test]
+    fn test_css_charset_rule() {
+        assert_eq!(
+            determine_encoding(b"@charset \"iso-8859-2\";\nbody {}", None, None),
+            (ISO_8859_2, CssEncodingSource::CharsetRule)
+        );
+        assert_eq!(
+            determine_encoding(b"@charset \"utf-16\";", None, None),
+            (UTF_8, CssEncodingSource::CharsetRule)
+        );
+        assert_eq!(
+            determine_encoding(b"@charset \"UTF-16BE\";", None, Some(GBK)),
+            (UTF_8, CssEncodingSource::CharsetRule)
+        );
+    }
+
+    #[test]
+    fn test_css_charset_rule_exact_pattern() {
+        // Anything other than the exact byte pattern is ignored.
+        assert_eq!(
+            determine_encoding(b"@charset 'iso-8859-2';", None, None),
+            (UTF_8, CssEncodingSource::Default)
+        );
+        assert_eq!(
+            determine_encoding(b"@CHARSET \"iso-8859-2\";", None, None),
+            (UTF_8, CssEncodingSource::Default)
+        );
+        assert_eq!(
+            determine_encoding(b"@charset  \"iso-8859-2\";", None, None),
+            (UTF_8, CssEncodingSource::Default)
+        );
+        assert_eq!(
+            determine_encoding(b"@charset \"iso-8859-2\" ;", None, None),
+            (UTF_8, CssEncodingSource::Default)
+        );
+        assert_eq!(
+            determine_encoding(b" @charset \"iso-8859-2\";", None, None),
+            (UTF_8, CssEncodingSource::Default)
+        );
+        assert_eq!(
+            determine_encoding(b"@charset \"bogus\";", None, Some(GBK)),
+            (GBK, CssEncodingSource::Environment)
+        );
+        let mut bytes = b"@charset \"".to_vec();
+        bytes.extend_from_slice(&[b' '; 1024][..]);
+        bytes.extend_from_slice(b"gbk\";");
+        assert_eq!(
+            determine_encoding(&bytes[..], None, None),
+            (UTF_8, CssEncodingSource::Default)
+        );
+    }
+
+    #[test]
+    fn test_css_precedence() {
+        let css = b"@charset \"iso-8859-2\";";
+        assert_eq!(
+            determine_encoding(b"\xEF\xBB\xBF@charset \"iso-8859-2\";", Some(b"gbk"), None),
+            (UTF_8, CssEncodingSource::Bom)
+        );
+        assert_eq!(
+            determine_encoding(b"\xFF\xFEa\x00", None, None),
+            (UTF_16LE, CssEncodingSource::Bom)
+        );
+        assert_eq!(
+            determine_encoding(css, Some(b"gbk"), Some(BIG5)),
+            (GBK, CssEncodingSource::Protocol)
+        );
+        assert_eq!(
+            determine_encoding(css, Some(b"bogus"), Some(BIG5)),
+            (ISO_8859_2, CssEncodingSource::CharsetRule)
+        );
+        assert_eq!(
+            determine_encoding(b"body {}", None, Some(BIG5)),
+            (BIG5, CssEncodingSource::Environment)
+        );
+        assert_eq!(determine_encoding(b"", None, None), (UTF_8, CssEncodingSource::Default));
+    }
+}
@@ -10,6 +10,7 @@
 use super::*;
 use data::*;
 use handles::*;
+use iso_2022_jp::is_mapped_for_two_byte_encode;
 use variant::*;
 // Rust 1.14.0 requires the following despite the asterisk above.
 use super::in_inclusive_range16;
@@ -238,6 +239,16 @@ impl EucJpEncoder {
         byte_length.checked_add(1)
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        if c <= '\u{7F}' || c == '\u{A5}' || c == '\u{203E}' {
+            return true;
+        }
+        if c > '\u{FFFF}' {
+            return false;
+        }
+        is_mapped_for_two_byte_encode(c as u16)
+    }
+
     ascii_compatible_bmp_encoder_functions!(
         {
             // Lunde says 60% Hiragana, 30% Kanji, 10% Katakana
@@ -242,6 +242,26 @@ impl EucKrEncoder {
         byte_length.checked_add(1)
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        if c <= '\u{7F}' {
+            return true;
+        }
+        if c > '\u{FFFF}' {
+            return false;
+        }
+        let bmp = c as u16;
+        if in_range16(bmp, 0xAC00, 0xD7A4) {
+            // The CP949 extension covers the Hangul syllables that KS X 1001
+            // doesn't.
+            true
+        } else if in_range16(bmp, 0x33DE, 0xFF01) {
+            (in_range16(bmp, 0x4E00, 0x9F9D) || in_range16(bmp, 0xF900, 0xFA0C))
+                && position(&KSX1001_HANJA[..], bmp).is_some()
+        } else {
+            ksx1001_encode_misc(bmp).is_some()
+        }
+    }
+
     ascii_compatible_bmp_encoder_functions!(
         {
             let bmp_minus_hangul_start = bmp.wrapping_sub(0xAC00);
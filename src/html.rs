@@ -0,0 +1,333 @@
+// Copyright 2015-2016 Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encoding sniffing for HTML documents.
+//!
+//! This module implements the [_encoding sniffing algorithm_][1] of the HTML
+//! Standard and the [_change the encoding_][2] steps that run when a `<meta>`
+//! encountered during parsing disagrees with a tentative guess. The
+//! heuristic autodetection step and the step that inherits the encoding of
+//! a same-origin parent document are left to the caller, who can supply
+//! their result as the fallback.
+//!
+//! [1]: https://html.spec.whatwg.org/multipage/parsing.html#encoding-sniffing-algorithm
+//! [2]: https://html.spec.whatwg.org/multipage/parsing.html#changing-the-encoding-while-parsing
+
+use super::Decoder;
+use super::Encoding;
+use super::UTF_16BE;
+use super::UTF_16LE;
+use super::UTF_8;
+use super::WINDOWS_1252;
+use super::X_USER_DEFINED;
+use prescan::prescan;
+
+/// How sure the HTML parser is about the encoding of the document.
+///
+/// Available to Rust only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Confidence {
+    /// The encoding was guessed or chosen by the user and a later `<meta>`
+    /// may change it.
+    Tentative,
+    /// The encoding was declared by a BOM or the transport layer, or a
+    /// `<meta>` has already been honored.
+    Certain,
+    /// The encoding was known to be definite before sniffing.
+    Irrelevant,
+}
+
+/// Runs the HTML encoding sniffing algorithm and owns the resulting
+/// [`Decoder`][1].
+///
+/// Configure the inputs using the setters, call [`sniff()`][2] with the
+/// start of the byte stream and decode the stream from its first byte with
+/// [`decoder_mut()`][3]. If the parser later encounters a `<meta>` that
+/// declares an encoding, call [`change_encoding()`][4]; if it returns `true`,
+/// throw away the output decoded so far and decode the stream again from
+/// its first byte with the new decoder.
+///
+/// Available to Rust only.
+///
+/// [1]: ../struct.Decoder.html
+/// [2]: #method.sniff
+/// [3]: #method.decoder_mut
+/// [4]: #method.change_encoding
+pub struct EncodingSniffer {
+    definite: Option<&'static Encoding>,
+    user_override: Option<&'static Encoding>,
+    transport: Option<&'static Encoding>,
+    fallback: &'static Encoding,
+    confidence: Confidence,
+    decoder: Option<Decoder>,
+}
+
+impl EncodingSniffer {
+    /// Creates a sniffer that falls back to `fallback` when nothing else
+    /// determines the encoding.
+    ///
+    /// Available to Rust only.
+    pub fn new(fallback: &'static Encoding) -> EncodingSniffer {
+        EncodingSniffer {
+            definite: None,
+            user_override: None,
+            transport: None,
+            fallback,
+            confidence: Confidence::Tentative,
+            decoder: None,
+        }
+    }
+
+    /// Creates a sniffer that falls back to the encoding browsers use for
+    /// legacy content in `locale` as given by
+    /// [`Encoding::fallback_for_locale()`][1].
+    ///
+    /// Available to Rust only.
+    ///
+    /// [1]: ../struct.Encoding.html#method.fallback_for_locale
+    pub fn for_locale(locale: &str) -> EncodingSniffer {
+        EncodingSniffer::new(Encoding::fallback_for_locale(locale))
+    }
+
+    /// Sets an encoding that is known to be definite, which makes the
+    /// confidence irrelevant. Only a BOM takes precedence.
+    ///
+    /// Available to Rust only.
+    pub fn set_definite_encoding(&mut self, encoding: Option<&'static Encoding>) {
+        self.definite = encoding;
+    }
+
+    /// Sets the encoding the user has chosen to override the document's
+    /// encoding with.
+    ///
+    /// As in the HTML Standard, the confidence in the user's choice is
+    /// tentative, so a `<meta>` encountered while parsing can still change
+    /// the encoding.
+    ///
+    /// Available to Rust only.
+    pub fn set_user_override(&mut self, encoding: Option<&'static Encoding>) {
+        self.user_override = encoding;
+    }
+
+    /// Sets the encoding label from the transport layer, e.g. the `charset`
+    /// of the HTTP `Content-Type`. Labels that
+    /// [`Encoding::for_label()`][1] doesn't recognize are ignored.
+    ///
+    /// Available to Rust only.
+    ///
+    /// [1]: ../struct.Encoding.html#method.for_label
+    pub fn set_transport_label(&mut self, label: &[u8]) {
+        self.transport = Encoding::for_label(label);
+    }
+
+    /// Determines the encoding from the start of the byte stream and creates
+    /// the decoder.
+    ///
+    /// `bytes` should be the first 1024 bytes of the stream or the whole
+    /// stream if it is shorter. The steps are tried in the order of the HTML
+    /// Standard: a BOM and the transport layer are certain, a definite
+    /// encoding makes the confidence irrelevant, and the user override, the
+    /// `<meta>` prescan and the fallback are tentative.
+    ///
+    /// Available to Rust only.
+    pub fn sniff(&mut self, bytes: &[u8]) -> (&'static Encoding, Confidence) {
+        if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+            self.confidence = Confidence::Certain;
+            self.decoder = Some(encoding.new_decoder_with_bom_removal());
+            return (encoding, self.confidence);
+        }
+        let (encoding, confidence) = if let Some(encoding) = self.definite {
+            (encoding, Confidence::Irrelevant)
+        } else if let Some(encoding) = self.user_override {
+            (encoding, Confidence::Tentative)
+        } else if let Some(encoding) = self.transport {
+            (encoding, Confidence::Certain)
+        } else if let Some(encoding) = prescan(bytes) {
+            (encoding, Confidence::Tentative)
+        } else {
+            (self.fallback, Confidence::Tentative)
+        };
+        self.confidence = confidence;
+        self.decoder = Some(encoding.new_decoder_without_bom_handling());
+        (encoding, confidence)
+    }
+
+    /// The encoding determined by [`sniff()`][1] and any later
+    /// [`change_encoding()`][2] or `None` before sniffing.
+    ///
+    /// Available to Rust only.
+    ///
+    /// [1]: #method.sniff
+    /// [2]: #method.change_encoding
+    pub fn encoding(&self) -> Option<&'static Encoding> {
+        self.decoder.as_ref().map(Decoder::encoding)
+    }
+
+    /// The current confidence.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn confidence(&self) -> Confidence {
+        self.confidence
+    }
+
+    /// The decoder for the stream or `None` before sniffing.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn decoder_mut(&mut self) -> Option<&mut Decoder> {
+        self.decoder.as_mut()
+    }
+
+    /// Handles an encoding declared by a `<meta>` encountered while parsing.
+    ///
+    /// Does nothing unless the confidence is tentative. As in the HTML
+    /// Standard, UTF-16BE and UTF-16LE are taken to mean UTF-8 and
+    /// x-user-defined is taken to mean windows-1252. If the declared encoding
+    /// matches the current one or the current one is UTF-16, the confidence
+    /// becomes certain. Otherwise, the decoder is replaced with a fresh one
+    /// for the declared encoding, the confidence becomes certain and `true`
+    /// is returned to tell the caller to restart decoding from the start of
+    /// the stream.
+    ///
+    /// Available to Rust only.
+    pub fn change_encoding(&mut self, declared: &'static Encoding) -> bool {
+        if self.confidence != Confidence::Tentative {
+            return false;
+        }
+        let current = match self.encoding() {
+            Some(current) => current,
+            None => return false,
+        };
+        self.confidence = Confidence::Certain;
+        if current == UTF_16BE || current == UTF_16LE {
+            return false;
+        }
+        let declared = if declared == UTF_16BE || declared == UTF_16LE {
+            UTF_8
+        } else if declared == X_USER_DEFINED {
+            WINDOWS_1252
+        } else {
+            declared
+        };
+        if declared == current {
+            return false;
+        }
+        self.decoder = Some(declared.new_decoder_without_bom_handling());
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    fn decode_all(sniffer: &mut EncodingSniffer, bytes: &[u8]) -> String {
+        let decoder = sniffer.decoder_mut().unwrap();
+        let capacity = decoder.max_utf8_buffer_length(bytes.len()).unwrap();
+        let mut string = String::with_capacity(capacity);
+        let (result, _, _) = decoder.decode_to_string(bytes, &mut string, true);
+        assert_eq!(result, CoderResult::InputEmpty);
+        string
+    }
+
+    #[test]
+    fn test_sniffer_precedence() {
+        let meta = b"<meta charset=euc-kr>";
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        assert_eq!(sniffer.encoding(), None);
+        assert_eq!(sniffer.sniff(b"<p>"), (WINDOWS_1252, Confidence::Tentative));
+        assert_eq!(sniffer.sniff(meta), (EUC_KR, Confidence::Tentative));
+        sniffer.set_transport_label(b"bogus");
+        assert_eq!(sniffer.sniff(meta), (EUC_KR, Confidence::Tentative));
+        sniffer.set_transport_label(b" Shift_JIS ");
+        assert_eq!(sniffer.sniff(meta), (SHIFT_JIS, Confidence::Certain));
+        sniffer.set_user_override(Some(BIG5));
+        assert_eq!(sniffer.sniff(meta), (BIG5, Confidence::Tentative));
+        sniffer.set_definite_encoding(Some(GBK));
+        assert_eq!(sniffer.sniff(meta), (GBK, Confidence::Irrelevant));
+        assert_eq!(sniffer.sniff(b"\xFE\xFF\x00a"), (UTF_16BE, Confidence::Certain));
+        assert_eq!(sniffer.encoding(), Some(UTF_16BE));
+    }
+
+    #[test]
+    fn test_sniffer_locale_fallback() {
+        let mut sniffer = EncodingSniffer::for_locale("ja-JP");
+        assert_eq!(sniffer.sniff(b"<p>"), (SHIFT_JIS, Confidence::Tentative));
+        let mut sniffer = EncodingSniffer::for_locale("de");
+        assert_eq!(sniffer.sniff(b"<p>"), (WINDOWS_1252, Confidence::Tentative));
+    }
+
+    #[test]
+    fn test_sniffer_decoder() {
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        sniffer.sniff(b"\xEF\xBB\xBF\xC3\xA4");
+        assert_eq!(decode_all(&mut sniffer, b"\xEF\xBB\xBF\xC3\xA4"), "\u{E4}");
+
+        // Without a BOM, the decoder doesn't sniff one later.
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        sniffer.set_transport_label(b"utf-8");
+        sniffer.sniff(b"<p>");
+        assert_eq!(decode_all(&mut sniffer, b"\xEF\xBB\xBFa"), "\u{FEFF}a");
+    }
+
+    #[test]
+    fn test_sniffer_change_encoding() {
+        let bytes = b"<p>\xE4</p><meta charset=koi8-r>";
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        assert!(!sniffer.change_encoding(KOI8_R));
+        sniffer.sniff(&bytes[..9]);
+        assert_eq!(decode_all(&mut sniffer, &bytes[..]).chars().nth(3), Some('\u{E4}'));
+        assert!(sniffer.change_encoding(KOI8_R));
+        assert_eq!(sniffer.encoding(), Some(KOI8_R));
+        assert_eq!(sniffer.confidence(), Confidence::Certain);
+        assert_eq!(decode_all(&mut sniffer, &bytes[..]).chars().nth(3), Some('\u{414}'));
+        // Once certain, later declarations are ignored.
+        assert!(!sniffer.change_encoding(GBK));
+        assert_eq!(sniffer.encoding(), Some(KOI8_R));
+    }
+
+    #[test]
+    fn test_sniffer_change_encoding_overrides() {
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        sniffer.sniff(b"<p>");
+        assert!(!sniffer.change_encoding(X_USER_DEFINED));
+        assert_eq!(sniffer.confidence(), Confidence::Certain);
+
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        sniffer.sniff(b"<p>");
+        assert!(sniffer.change_encoding(UTF_16LE));
+        assert_eq!(sniffer.encoding(), Some(UTF_8));
+
+        let mut sniffer = EncodingSniffer::new(UTF_16LE);
+        sniffer.sniff(b"<p>");
+        assert!(!sniffer.change_encoding(GBK));
+        assert_eq!(sniffer.encoding(), Some(UTF_16LE));
+        assert_eq!(sniffer.confidence(), Confidence::Certain);
+    }
+
+    #[test]
+    fn test_sniffer_user_override_is_tentative() {
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        sniffer.set_user_override(Some(GBK));
+        sniffer.set_transport_label(b"euc-kr");
+        assert_eq!(sniffer.sniff(b"<meta charset=koi8-r>"), (GBK, Confidence::Tentative));
+        assert!(sniffer.change_encoding(BIG5));
+        assert_eq!(sniffer.encoding(), Some(BIG5));
+        assert_eq!(sniffer.confidence(), Confidence::Certain);
+
+        let mut sniffer = EncodingSniffer::new(WINDOWS_1252);
+        sniffer.set_user_override(Some(GBK));
+        sniffer.sniff(b"<p>");
+        assert!(!sniffer.change_encoding(GBK));
+        assert_eq!(sniffer.encoding(), Some(GBK));
+        assert_eq!(sniffer.confidence(), Confidence::Certain);
+    }
+}
@@ -357,7 +357,7 @@ impl Iso2022JpDecoder {
 }
 
 #[cfg_attr(feature = "cargo-clippy", allow(if_let_redundant_pattern_matching, if_same_then_else))]
-fn is_mapped_for_two_byte_encode(bmp: u16) -> bool {
+pub fn is_mapped_for_two_byte_encode(bmp: u16) -> bool {
     // The code below uses else after return to
     // keep the same structure as in EUC-JP.
     // Lunde says 60% Hiragana, 30% Kanji, 10% Katakana
@@ -466,6 +466,19 @@ impl Iso2022JpEncoder {
         checked_add(3, byte_length.checked_mul(3))
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        if c == '\u{0E}' || c == '\u{0F}' || c == '\u{1B}' {
+            return false;
+        }
+        if c <= '\u{7F}' || c == '\u{A5}' || c == '\u{203E}' {
+            return true;
+        }
+        if c > '\u{FFFF}' {
+            return false;
+        }
+        is_mapped_for_two_byte_encode(c as u16)
+    }
+
     encoder_functions!(
         {
             match self.state {
@@ -733,7 +733,12 @@ mod data;
 mod handles;
 mod variant;
 
+pub mod css;
+pub mod html;
 pub mod mem;
+pub mod mime;
+pub mod prescan;
+pub mod xml;
 
 use ascii::ascii_valid_up_to;
 use ascii::iso_2022_jp_ascii_valid_up_to;
@@ -744,6 +749,7 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::hash::Hash;
 use std::hash::Hasher;
+use std::ops::Range;
 
 #[cfg(feature = "serde")]
 use serde::de::Visitor;
@@ -757,6 +763,334 @@ use serde::{Deserialize, Deserializer, Serialize, Serializer};
 /// with a state transition escape.
 const NCR_EXTRA: usize = 10; // &#1114111;
 
+/// Whether every encoder can encode `s` regardless of its state, i.e. `s` is
+/// ASCII without the ISO-2022-JP shift and escape bytes.
+fn is_always_mappable(s: &str) -> bool {
+    s.bytes().all(|b| b < 0x80 && b != 0x0E && b != 0x0F && b != 0x1B)
+}
+
+static HEX_UPPER: [u8; 16] = *b"0123456789ABCDEF";
+
+/// The number of most recently consumed bytes a `Decoder` remembers. This
+/// has to cover the longest malformed sequence plus the bytes consumed after
+/// it, because the malformed sequence may have started in an earlier input
+/// buffer.
+const RECENT_BYTES_LENGTH: usize = 8;
+
+const LONGEST_PLATFORM_NAME_LENGTH: usize = 18; // ibm-5348-p100-1997
+
+/// Charset names used by Java, ICU and Python that are not labels in the
+/// Encoding Standard or that are labels of a superset of the charset they
+/// name, lowercased and with underscores replaced with hyphens, with the
+/// label of the closest encoding and whether the match is exact. Sorted by
+/// name.
+static PLATFORM_NAMES_SORTED: [(&str, &str, bool); 73] = [
+    ("ascii", "windows-1252", false),
+    ("big5hkscs", "big5", true),
+    ("cp874", "windows-874", true),
+    ("cp932", "shift_jis", true),
+    ("cp936", "gbk", true),
+    ("cp949", "euc-kr", true),
+    ("cp950", "big5", false),
+    ("euc-cn", "gbk", false),
+    ("euc-jis-2004", "euc-jp", false),
+    ("euc-jisx0213", "euc-jp", false),
+    ("eucjp", "euc-jp", true),
+    ("euckr", "euc-kr", true),
+    ("gb2312", "gbk", false),
+    ("ibm-1252", "windows-1252", true),
+    ("ibm-1363", "euc-kr", false),
+    ("ibm-1386", "gbk", false),
+    ("ibm-33722", "euc-jp", false),
+    ("ibm-5348-p100-1997", "windows-1252", true),
+    ("ibm-5471", "big5", false),
+    ("ibm-874", "windows-874", false),
+    ("ibm-943", "shift_jis", false),
+    ("ibm-943-p15a-2003", "shift_jis", true),
+    ("iso-2022-jp-1", "iso-2022-jp", false),
+    ("iso-2022-jp-2", "iso-2022-jp", false),
+    ("iso-8859-1", "windows-1252", false),
+    ("iso-8859-11", "windows-874", false),
+    ("iso-8859-9", "windows-1254", false),
+    ("iso2022-jp", "iso-2022-jp", true),
+    ("iso2022-jp-2", "iso-2022-jp", false),
+    ("iso2022jp", "iso-2022-jp", true),
+    ("iso8859-1", "windows-1252", false),
+    ("iso8859-11", "windows-874", false),
+    ("iso8859-9", "windows-1254", false),
+    ("l1", "windows-1252", false),
+    ("l5", "windows-1254", false),
+    ("latin-1", "windows-1252", false),
+    ("latin1", "windows-1252", false),
+    ("latin5", "windows-1254", false),
+    ("mac-cyrillic", "x-mac-cyrillic", true),
+    ("mac-roman", "macintosh", true),
+    ("maccyrillic", "x-mac-cyrillic", true),
+    ("ms936", "gbk", true),
+    ("ms949", "euc-kr", true),
+    ("ms950", "big5", false),
+    ("mskanji", "shift_jis", true),
+    ("s-jis", "shift_jis", true),
+    ("s-jis-2004", "shift_jis", false),
+    ("s-jisx0213", "shift_jis", false),
+    ("shift-jis-2004", "shift_jis", false),
+    ("shift-jisx0213", "shift_jis", false),
+    ("shiftjis", "shift_jis", true),
+    ("shiftjis2004", "shift_jis", false),
+    ("shiftjisx0213", "shift_jis", false),
+    ("sjis-2004", "shift_jis", false),
+    ("sjisx0213", "shift_jis", false),
+    ("tis-620", "windows-874", false),
+    ("u-jis", "euc-jp", true),
+    ("u8", "utf-8", true),
+    ("uhc", "euc-kr", true),
+    ("ujis", "euc-jp", true),
+    ("us-ascii", "windows-1252", false),
+    ("utf-16", "utf-16le", false),
+    ("utf-16-be", "utf-16be", true),
+    ("utf-16-le", "utf-16le", true),
+    ("utf-8-sig", "utf-8", true),
+    ("windows-936", "gbk", true),
+    ("x-euc-jp-linux", "euc-jp", false),
+    ("x-ibm874", "windows-874", false),
+    ("x-mswin-936", "gbk", true),
+    ("x-sjis-0213", "shift_jis", false),
+    ("x-windows-874", "windows-874", true),
+    ("x-windows-949", "euc-kr", true),
+    ("x-windows-950", "big5", false),
+];
+
+const LONGEST_DATABASE_NAME_LENGTH: usize = 12; // shiftjis2004
+
+/// MySQL character set names with the label of the corresponding encoding.
+/// Sorted by name.
+static MYSQL_CHARSETS_SORTED: [(&str, &str); 30] = [
+    ("ascii", "windows-1252"),
+    ("big5", "big5"),
+    ("cp1250", "windows-1250"),
+    ("cp1251", "windows-1251"),
+    ("cp1256", "windows-1256"),
+    ("cp1257", "windows-1257"),
+    ("cp866", "ibm866"),
+    ("cp932", "shift_jis"),
+    ("eucjpms", "euc-jp"),
+    ("euckr", "euc-kr"),
+    ("gb18030", "gb18030"),
+    ("gb2312", "gbk"),
+    ("gbk", "gbk"),
+    ("greek", "iso-8859-7"),
+    ("hebrew", "iso-8859-8"),
+    ("koi8r", "koi8-r"),
+    ("koi8u", "koi8-u"),
+    ("latin1", "windows-1252"),
+    ("latin2", "iso-8859-2"),
+    ("latin5", "windows-1254"),
+    ("latin7", "iso-8859-13"),
+    ("macroman", "macintosh"),
+    ("sjis", "shift_jis"),
+    ("tis620", "windows-874"),
+    ("ujis", "euc-jp"),
+    ("utf16", "utf-16be"),
+    ("utf16le", "utf-16le"),
+    ("utf8", "utf-8"),
+    ("utf8mb3", "utf-8"),
+    ("utf8mb4", "utf-8"),
+];
+
+/// PostgreSQL encoding names and aliases, lowercased and without
+/// non-alphanumeric characters as PostgreSQL compares them, with the label of
+/// the corresponding encoding. Sorted by name.
+static POSTGRESQL_ENCODINGS_SORTED: [(&str, &str); 77] = [
+    ("abc", "windows-1258"),
+    ("alt", "ibm866"),
+    ("big5", "big5"),
+    ("euccn", "gbk"),
+    ("eucjis2004", "euc-jp"),
+    ("eucjp", "euc-jp"),
+    ("euckr", "euc-kr"),
+    ("gb18030", "gb18030"),
+    ("gbk", "gbk"),
+    ("iso88591", "windows-1252"),
+    ("iso885910", "iso-8859-10"),
+    ("iso885913", "iso-8859-13"),
+    ("iso885914", "iso-8859-14"),
+    ("iso885915", "iso-8859-15"),
+    ("iso885916", "iso-8859-16"),
+    ("iso88592", "iso-8859-2"),
+    ("iso88593", "iso-8859-3"),
+    ("iso88594", "iso-8859-4"),
+    ("iso88595", "iso-8859-5"),
+    ("iso88596", "iso-8859-6"),
+    ("iso88597", "iso-8859-7"),
+    ("iso88598", "iso-8859-8"),
+    ("iso88599", "windows-1254"),
+    ("koi8", "koi8-r"),
+    ("koi8r", "koi8-r"),
+    ("koi8u", "koi8-u"),
+    ("latin1", "windows-1252"),
+    ("latin10", "iso-8859-16"),
+    ("latin2", "iso-8859-2"),
+    ("latin3", "iso-8859-3"),
+    ("latin4", "iso-8859-4"),
+    ("latin5", "windows-1254"),
+    ("latin6", "iso-8859-10"),
+    ("latin7", "iso-8859-13"),
+    ("latin8", "iso-8859-14"),
+    ("latin9", "iso-8859-15"),
+    ("mskanji", "shift_jis"),
+    ("shiftjis", "shift_jis"),
+    ("shiftjis2004", "shift_jis"),
+    ("sjis", "shift_jis"),
+    ("tcvn", "windows-1258"),
+    ("tcvn5712", "windows-1258"),
+    ("uhc", "euc-kr"),
+    ("unicode", "utf-8"),
+    ("utf8", "utf-8"),
+    ("vscii", "windows-1258"),
+    ("win", "windows-1251"),
+    ("win1250", "windows-1250"),
+    ("win1251", "windows-1251"),
+    ("win1252", "windows-1252"),
+    ("win1253", "windows-1253"),
+    ("win1254", "windows-1254"),
+    ("win1255", "windows-1255"),
+    ("win1256", "windows-1256"),
+    ("win1257", "windows-1257"),
+    ("win1258", "windows-1258"),
+    ("win866", "ibm866"),
+    ("win874", "windows-874"),
+    ("win932", "shift_jis"),
+    ("win936", "gbk"),
+    ("win949", "euc-kr"),
+    ("win950", "big5"),
+    ("windows1250", "windows-1250"),
+    ("windows1251", "windows-1251"),
+    ("windows1252", "windows-1252"),
+    ("windows1253", "windows-1253"),
+    ("windows1254", "windows-1254"),
+    ("windows1255", "windows-1255"),
+    ("windows1256", "windows-1256"),
+    ("windows1257", "windows-1257"),
+    ("windows1258", "windows-1258"),
+    ("windows866", "ibm866"),
+    ("windows874", "windows-874"),
+    ("windows932", "shift_jis"),
+    ("windows936", "gbk"),
+    ("windows949", "euc-kr"),
+    ("windows950", "big5"),
+];
+
+/// Looks up `name` in a table of database charset names after
+/// ASCII-lowercasing it and removing non-alphanumeric characters.
+fn for_database_name(names: &[(&str, &str)], name: &[u8]) -> Option<&'static Encoding> {
+    let mut normalized = [0u8; LONGEST_DATABASE_NAME_LENGTH];
+    let mut normalized_len = 0usize;
+    for &byte in name.iter().filter(|b| b.is_ascii_alphanumeric()) {
+        if normalized_len == LONGEST_DATABASE_NAME_LENGTH {
+            return None;
+        }
+        normalized[normalized_len] = byte.to_ascii_lowercase();
+        normalized_len += 1;
+    }
+    let normalized = &normalized[..normalized_len];
+    match names.binary_search_by(|&(probe, _)| probe.as_bytes().cmp(normalized)) {
+        Ok(i) => Encoding::for_label(names[i].1.as_bytes()),
+        Err(_) => None,
+    }
+}
+
+/// Languages whose fallback encoding isn't windows-1252 with their fallback
+/// encodings in Gecko. Chinese is handled separately. Sorted by language.
+static LOCALE_FALLBACKS: [(&str, &Encoding); 31] = [
+    ("ar", &WINDOWS_1256_INIT),
+    ("ba", &WINDOWS_1251_INIT),
+    ("be", &WINDOWS_1251_INIT),
+    ("bg", &WINDOWS_1251_INIT),
+    ("cs", &WINDOWS_1250_INIT),
+    ("el", &ISO_8859_7_INIT),
+    ("et", &WINDOWS_1257_INIT),
+    ("fa", &WINDOWS_1256_INIT),
+    ("he", &WINDOWS_1255_INIT),
+    ("hr", &WINDOWS_1250_INIT),
+    ("hu", &ISO_8859_2_INIT),
+    ("ja", &SHIFT_JIS_INIT),
+    ("kk", &WINDOWS_1251_INIT),
+    ("ko", &EUC_KR_INIT),
+    ("ku", &WINDOWS_1254_INIT),
+    ("ky", &WINDOWS_1251_INIT),
+    ("lt", &WINDOWS_1257_INIT),
+    ("lv", &WINDOWS_1257_INIT),
+    ("mk", &WINDOWS_1251_INIT),
+    ("pl", &ISO_8859_2_INIT),
+    ("ru", &WINDOWS_1251_INIT),
+    ("sah", &WINDOWS_1251_INIT),
+    ("sk", &WINDOWS_1250_INIT),
+    ("sl", &ISO_8859_2_INIT),
+    ("sr", &WINDOWS_1251_INIT),
+    ("tg", &WINDOWS_1251_INIT),
+    ("th", &WINDOWS_874_INIT),
+    ("tr", &WINDOWS_1254_INIT),
+    ("tt", &WINDOWS_1251_INIT),
+    ("uk", &WINDOWS_1251_INIT),
+    ("vi", &WINDOWS_1258_INIT),
+];
+
+/// Country code top-level domains whose fallback encoding isn't
+/// windows-1252 with the fallback encoding of the main language of the
+/// country. Sorted by domain.
+static TLD_FALLBACKS: [(&str, &Encoding); 50] = [
+    ("ae", &WINDOWS_1256_INIT),
+    ("af", &WINDOWS_1256_INIT),
+    ("bg", &WINDOWS_1251_INIT),
+    ("bh", &WINDOWS_1256_INIT),
+    ("by", &WINDOWS_1251_INIT),
+    ("cn", &GBK_INIT),
+    ("cy", &ISO_8859_7_INIT),
+    ("cz", &WINDOWS_1250_INIT),
+    ("dz", &WINDOWS_1256_INIT),
+    ("ee", &WINDOWS_1257_INIT),
+    ("eg", &WINDOWS_1256_INIT),
+    ("gr", &ISO_8859_7_INIT),
+    ("hk", &BIG5_INIT),
+    ("hr", &WINDOWS_1250_INIT),
+    ("hu", &ISO_8859_2_INIT),
+    ("il", &WINDOWS_1255_INIT),
+    ("iq", &WINDOWS_1256_INIT),
+    ("ir", &WINDOWS_1256_INIT),
+    ("jo", &WINDOWS_1256_INIT),
+    ("jp", &SHIFT_JIS_INIT),
+    ("kg", &WINDOWS_1251_INIT),
+    ("kr", &EUC_KR_INIT),
+    ("kw", &WINDOWS_1256_INIT),
+    ("kz", &WINDOWS_1251_INIT),
+    ("lb", &WINDOWS_1256_INIT),
+    ("lt", &WINDOWS_1257_INIT),
+    ("lv", &WINDOWS_1257_INIT),
+    ("ly", &WINDOWS_1256_INIT),
+    ("ma", &WINDOWS_1256_INIT),
+    ("mk", &WINDOWS_1251_INIT),
+    ("mo", &BIG5_INIT),
+    ("om", &WINDOWS_1256_INIT),
+    ("pl", &ISO_8859_2_INIT),
+    ("qa", &WINDOWS_1256_INIT),
+    ("rs", &WINDOWS_1251_INIT),
+    ("ru", &WINDOWS_1251_INIT),
+    ("sa", &WINDOWS_1256_INIT),
+    ("sd", &WINDOWS_1256_INIT),
+    ("si", &ISO_8859_2_INIT),
+    ("sk", &WINDOWS_1250_INIT),
+    ("su", &WINDOWS_1251_INIT),
+    ("sy", &WINDOWS_1256_INIT),
+    ("th", &WINDOWS_874_INIT),
+    ("tj", &WINDOWS_1251_INIT),
+    ("tn", &WINDOWS_1256_INIT),
+    ("tr", &WINDOWS_1254_INIT),
+    ("tw", &BIG5_INIT),
+    ("ua", &WINDOWS_1251_INIT),
+    ("vn", &WINDOWS_1258_INIT),
+    ("ye", &WINDOWS_1256_INIT),
+];
+
 // BEGIN GENERATED CODE. PLEASE DO NOT EDIT.
 // Instead, please regenerate using generate-encoding-data.py
 
@@ -776,6 +1110,7 @@ const LONGEST_LABEL_LENGTH: usize = 19; // cseucpkdfmtjapanese
 /// items.
 pub static BIG5_INIT: Encoding = Encoding {
     name: "Big5",
+    id: EncodingId::Big5,
     variant: VariantEncoding::Big5,
 };
 
@@ -818,6 +1153,7 @@ pub static BIG5: &'static Encoding = &BIG5_INIT;
 /// items.
 pub static EUC_JP_INIT: Encoding = Encoding {
     name: "EUC-JP",
+    id: EncodingId::EucJp,
     variant: VariantEncoding::EucJp,
 };
 
@@ -856,6 +1192,7 @@ pub static EUC_JP: &'static Encoding = &EUC_JP_INIT;
 /// items.
 pub static EUC_KR_INIT: Encoding = Encoding {
     name: "EUC-KR",
+    id: EncodingId::EucKr,
     variant: VariantEncoding::EucKr,
 };
 
@@ -892,6 +1229,7 @@ pub static EUC_KR: &'static Encoding = &EUC_KR_INIT;
 /// items.
 pub static GBK_INIT: Encoding = Encoding {
     name: "GBK",
+    id: EncodingId::Gbk,
     variant: VariantEncoding::Gbk,
 };
 
@@ -934,6 +1272,7 @@ pub static GBK: &'static Encoding = &GBK_INIT;
 /// items.
 pub static IBM866_INIT: Encoding = Encoding {
     name: "IBM866",
+    id: EncodingId::Ibm866,
     variant: VariantEncoding::SingleByte(data::IBM866_DATA),
 };
 
@@ -968,6 +1307,7 @@ pub static IBM866: &'static Encoding = &IBM866_INIT;
 /// items.
 pub static ISO_2022_JP_INIT: Encoding = Encoding {
     name: "ISO-2022-JP",
+    id: EncodingId::Iso2022Jp,
     variant: VariantEncoding::Iso2022Jp,
 };
 
@@ -1004,6 +1344,7 @@ pub static ISO_2022_JP: &'static Encoding = &ISO_2022_JP_INIT;
 /// items.
 pub static ISO_8859_10_INIT: Encoding = Encoding {
     name: "ISO-8859-10",
+    id: EncodingId::Iso10,
     variant: VariantEncoding::SingleByte(data::ISO_8859_10_DATA),
 };
 
@@ -1038,6 +1379,7 @@ pub static ISO_8859_10: &'static Encoding = &ISO_8859_10_INIT;
 /// items.
 pub static ISO_8859_13_INIT: Encoding = Encoding {
     name: "ISO-8859-13",
+    id: EncodingId::Iso13,
     variant: VariantEncoding::SingleByte(data::ISO_8859_13_DATA),
 };
 
@@ -1072,6 +1414,7 @@ pub static ISO_8859_13: &'static Encoding = &ISO_8859_13_INIT;
 /// items.
 pub static ISO_8859_14_INIT: Encoding = Encoding {
     name: "ISO-8859-14",
+    id: EncodingId::Iso14,
     variant: VariantEncoding::SingleByte(data::ISO_8859_14_DATA),
 };
 
@@ -1106,6 +1449,7 @@ pub static ISO_8859_14: &'static Encoding = &ISO_8859_14_INIT;
 /// items.
 pub static ISO_8859_15_INIT: Encoding = Encoding {
     name: "ISO-8859-15",
+    id: EncodingId::Iso15,
     variant: VariantEncoding::SingleByte(data::ISO_8859_15_DATA),
 };
 
@@ -1139,6 +1483,7 @@ pub static ISO_8859_15: &'static Encoding = &ISO_8859_15_INIT;
 /// items.
 pub static ISO_8859_16_INIT: Encoding = Encoding {
     name: "ISO-8859-16",
+    id: EncodingId::Iso16,
     variant: VariantEncoding::SingleByte(data::ISO_8859_16_DATA),
 };
 
@@ -1173,6 +1518,7 @@ pub static ISO_8859_16: &'static Encoding = &ISO_8859_16_INIT;
 /// items.
 pub static ISO_8859_2_INIT: Encoding = Encoding {
     name: "ISO-8859-2",
+    id: EncodingId::Iso2,
     variant: VariantEncoding::SingleByte(data::ISO_8859_2_DATA),
 };
 
@@ -1205,6 +1551,7 @@ pub static ISO_8859_2: &'static Encoding = &ISO_8859_2_INIT;
 /// items.
 pub static ISO_8859_3_INIT: Encoding = Encoding {
     name: "ISO-8859-3",
+    id: EncodingId::Iso3,
     variant: VariantEncoding::SingleByte(data::ISO_8859_3_DATA),
 };
 
@@ -1237,6 +1584,7 @@ pub static ISO_8859_3: &'static Encoding = &ISO_8859_3_INIT;
 /// items.
 pub static ISO_8859_4_INIT: Encoding = Encoding {
     name: "ISO-8859-4",
+    id: EncodingId::Iso4,
     variant: VariantEncoding::SingleByte(data::ISO_8859_4_DATA),
 };
 
@@ -1269,6 +1617,7 @@ pub static ISO_8859_4: &'static Encoding = &ISO_8859_4_INIT;
 /// items.
 pub static ISO_8859_5_INIT: Encoding = Encoding {
     name: "ISO-8859-5",
+    id: EncodingId::Iso5,
     variant: VariantEncoding::SingleByte(data::ISO_8859_5_DATA),
 };
 
@@ -1301,6 +1650,7 @@ pub static ISO_8859_5: &'static Encoding = &ISO_8859_5_INIT;
 /// items.
 pub static ISO_8859_6_INIT: Encoding = Encoding {
     name: "ISO-8859-6",
+    id: EncodingId::Iso6,
     variant: VariantEncoding::SingleByte(data::ISO_8859_6_DATA),
 };
 
@@ -1334,6 +1684,7 @@ pub static ISO_8859_6: &'static Encoding = &ISO_8859_6_INIT;
 /// items.
 pub static ISO_8859_7_INIT: Encoding = Encoding {
     name: "ISO-8859-7",
+    id: EncodingId::Iso7,
     variant: VariantEncoding::SingleByte(data::ISO_8859_7_DATA),
 };
 
@@ -1371,6 +1722,7 @@ pub static ISO_8859_7: &'static Encoding = &ISO_8859_7_INIT;
 /// items.
 pub static ISO_8859_8_INIT: Encoding = Encoding {
     name: "ISO-8859-8",
+    id: EncodingId::Iso8,
     variant: VariantEncoding::SingleByte(data::ISO_8859_8_DATA),
 };
 
@@ -1406,6 +1758,7 @@ pub static ISO_8859_8: &'static Encoding = &ISO_8859_8_INIT;
 /// items.
 pub static ISO_8859_8_I_INIT: Encoding = Encoding {
     name: "ISO-8859-8-I",
+    id: EncodingId::Iso8I,
     variant: VariantEncoding::SingleByte(data::ISO_8859_8_DATA),
 };
 
@@ -1441,6 +1794,7 @@ pub static ISO_8859_8_I: &'static Encoding = &ISO_8859_8_I_INIT;
 /// items.
 pub static KOI8_R_INIT: Encoding = Encoding {
     name: "KOI8-R",
+    id: EncodingId::Koi8R,
     variant: VariantEncoding::SingleByte(data::KOI8_R_DATA),
 };
 
@@ -1473,6 +1827,7 @@ pub static KOI8_R: &'static Encoding = &KOI8_R_INIT;
 /// items.
 pub static KOI8_U_INIT: Encoding = Encoding {
     name: "KOI8-U",
+    id: EncodingId::Koi8U,
     variant: VariantEncoding::SingleByte(data::KOI8_U_DATA),
 };
 
@@ -1505,6 +1860,7 @@ pub static KOI8_U: &'static Encoding = &KOI8_U_INIT;
 /// items.
 pub static SHIFT_JIS_INIT: Encoding = Encoding {
     name: "Shift_JIS",
+    id: EncodingId::ShiftJis,
     variant: VariantEncoding::ShiftJis,
 };
 
@@ -1539,6 +1895,7 @@ pub static SHIFT_JIS: &'static Encoding = &SHIFT_JIS_INIT;
 /// items.
 pub static UTF_16BE_INIT: Encoding = Encoding {
     name: "UTF-16BE",
+    id: EncodingId::Utf16Be,
     variant: VariantEncoding::Utf16Be,
 };
 
@@ -1573,6 +1930,7 @@ pub static UTF_16BE: &'static Encoding = &UTF_16BE_INIT;
 /// items.
 pub static UTF_16LE_INIT: Encoding = Encoding {
     name: "UTF-16LE",
+    id: EncodingId::Utf16Le,
     variant: VariantEncoding::Utf16Le,
 };
 
@@ -1607,6 +1965,7 @@ pub static UTF_16LE: &'static Encoding = &UTF_16LE_INIT;
 /// items.
 pub static UTF_8_INIT: Encoding = Encoding {
     name: "UTF-8",
+    id: EncodingId::Utf8,
     variant: VariantEncoding::Utf8,
 };
 
@@ -1638,6 +1997,7 @@ pub static UTF_8: &'static Encoding = &UTF_8_INIT;
 /// items.
 pub static GB18030_INIT: Encoding = Encoding {
     name: "gb18030",
+    id: EncodingId::Gb18030,
     variant: VariantEncoding::Gb18030,
 };
 
@@ -1673,6 +2033,7 @@ pub static GB18030: &'static Encoding = &GB18030_INIT;
 /// items.
 pub static MACINTOSH_INIT: Encoding = Encoding {
     name: "macintosh",
+    id: EncodingId::Macintosh,
     variant: VariantEncoding::SingleByte(data::MACINTOSH_DATA),
 };
 
@@ -1706,6 +2067,7 @@ pub static MACINTOSH: &'static Encoding = &MACINTOSH_INIT;
 /// items.
 pub static REPLACEMENT_INIT: Encoding = Encoding {
     name: "replacement",
+    id: EncodingId::Replacement,
     variant: VariantEncoding::Replacement,
 };
 
@@ -1742,6 +2104,7 @@ pub static REPLACEMENT: &'static Encoding = &REPLACEMENT_INIT;
 /// items.
 pub static WINDOWS_1250_INIT: Encoding = Encoding {
     name: "windows-1250",
+    id: EncodingId::Windows1250,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1250_DATA),
 };
 
@@ -1774,6 +2137,7 @@ pub static WINDOWS_1250: &'static Encoding = &WINDOWS_1250_INIT;
 /// items.
 pub static WINDOWS_1251_INIT: Encoding = Encoding {
     name: "windows-1251",
+    id: EncodingId::Windows1251,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1251_DATA),
 };
 
@@ -1806,6 +2170,7 @@ pub static WINDOWS_1251: &'static Encoding = &WINDOWS_1251_INIT;
 /// items.
 pub static WINDOWS_1252_INIT: Encoding = Encoding {
     name: "windows-1252",
+    id: EncodingId::Windows1252,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1252_DATA),
 };
 
@@ -1839,6 +2204,7 @@ pub static WINDOWS_1252: &'static Encoding = &WINDOWS_1252_INIT;
 /// items.
 pub static WINDOWS_1253_INIT: Encoding = Encoding {
     name: "windows-1253",
+    id: EncodingId::Windows1253,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1253_DATA),
 };
 
@@ -1873,6 +2239,7 @@ pub static WINDOWS_1253: &'static Encoding = &WINDOWS_1253_INIT;
 /// items.
 pub static WINDOWS_1254_INIT: Encoding = Encoding {
     name: "windows-1254",
+    id: EncodingId::Windows1254,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1254_DATA),
 };
 
@@ -1906,6 +2273,7 @@ pub static WINDOWS_1254: &'static Encoding = &WINDOWS_1254_INIT;
 /// items.
 pub static WINDOWS_1255_INIT: Encoding = Encoding {
     name: "windows-1255",
+    id: EncodingId::Windows1255,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1255_DATA),
 };
 
@@ -1940,6 +2308,7 @@ pub static WINDOWS_1255: &'static Encoding = &WINDOWS_1255_INIT;
 /// items.
 pub static WINDOWS_1256_INIT: Encoding = Encoding {
     name: "windows-1256",
+    id: EncodingId::Windows1256,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1256_DATA),
 };
 
@@ -1972,6 +2341,7 @@ pub static WINDOWS_1256: &'static Encoding = &WINDOWS_1256_INIT;
 /// items.
 pub static WINDOWS_1257_INIT: Encoding = Encoding {
     name: "windows-1257",
+    id: EncodingId::Windows1257,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1257_DATA),
 };
 
@@ -2005,6 +2375,7 @@ pub static WINDOWS_1257: &'static Encoding = &WINDOWS_1257_INIT;
 /// items.
 pub static WINDOWS_1258_INIT: Encoding = Encoding {
     name: "windows-1258",
+    id: EncodingId::Windows1258,
     variant: VariantEncoding::SingleByte(data::WINDOWS_1258_DATA),
 };
 
@@ -2042,6 +2413,7 @@ pub static WINDOWS_1258: &'static Encoding = &WINDOWS_1258_INIT;
 /// items.
 pub static WINDOWS_874_INIT: Encoding = Encoding {
     name: "windows-874",
+    id: EncodingId::Windows874,
     variant: VariantEncoding::SingleByte(data::WINDOWS_874_DATA),
 };
 
@@ -2075,6 +2447,7 @@ pub static WINDOWS_874: &'static Encoding = &WINDOWS_874_INIT;
 /// items.
 pub static X_MAC_CYRILLIC_INIT: Encoding = Encoding {
     name: "x-mac-cyrillic",
+    id: EncodingId::MacCyrillic,
     variant: VariantEncoding::SingleByte(data::X_MAC_CYRILLIC_DATA),
 };
 
@@ -2107,6 +2480,7 @@ pub static X_MAC_CYRILLIC: &'static Encoding = &X_MAC_CYRILLIC_INIT;
 /// items.
 pub static X_USER_DEFINED_INIT: Encoding = Encoding {
     name: "x-user-defined",
+    id: EncodingId::UserDefined,
     variant: VariantEncoding::UserDefined,
 };
 
@@ -2569,6 +2943,384 @@ static ENCODINGS_IN_LABEL_SORT: [&'static Encoding; 219] = [
     &EUC_JP_INIT,
 ];
 
+static ENCODINGS: [&Encoding; 40] = [
+    &BIG5_INIT,
+    &EUC_JP_INIT,
+    &EUC_KR_INIT,
+    &GBK_INIT,
+    &IBM866_INIT,
+    &ISO_2022_JP_INIT,
+    &ISO_8859_10_INIT,
+    &ISO_8859_13_INIT,
+    &ISO_8859_14_INIT,
+    &ISO_8859_15_INIT,
+    &ISO_8859_16_INIT,
+    &ISO_8859_2_INIT,
+    &ISO_8859_3_INIT,
+    &ISO_8859_4_INIT,
+    &ISO_8859_5_INIT,
+    &ISO_8859_6_INIT,
+    &ISO_8859_7_INIT,
+    &ISO_8859_8_INIT,
+    &ISO_8859_8_I_INIT,
+    &KOI8_R_INIT,
+    &KOI8_U_INIT,
+    &SHIFT_JIS_INIT,
+    &UTF_16BE_INIT,
+    &UTF_16LE_INIT,
+    &UTF_8_INIT,
+    &GB18030_INIT,
+    &MACINTOSH_INIT,
+    &REPLACEMENT_INIT,
+    &WINDOWS_1250_INIT,
+    &WINDOWS_1251_INIT,
+    &WINDOWS_1252_INIT,
+    &WINDOWS_1253_INIT,
+    &WINDOWS_1254_INIT,
+    &WINDOWS_1255_INIT,
+    &WINDOWS_1256_INIT,
+    &WINDOWS_1257_INIT,
+    &WINDOWS_1258_INIT,
+    &WINDOWS_874_INIT,
+    &X_MAC_CYRILLIC_INIT,
+    &X_USER_DEFINED_INIT,
+];
+
+static ENCODING_IDS: [EncodingId; 40] = [
+    EncodingId::Big5,
+    EncodingId::EucJp,
+    EncodingId::EucKr,
+    EncodingId::Gbk,
+    EncodingId::Ibm866,
+    EncodingId::Iso2022Jp,
+    EncodingId::Iso10,
+    EncodingId::Iso13,
+    EncodingId::Iso14,
+    EncodingId::Iso15,
+    EncodingId::Iso16,
+    EncodingId::Iso2,
+    EncodingId::Iso3,
+    EncodingId::Iso4,
+    EncodingId::Iso5,
+    EncodingId::Iso6,
+    EncodingId::Iso7,
+    EncodingId::Iso8,
+    EncodingId::Iso8I,
+    EncodingId::Koi8R,
+    EncodingId::Koi8U,
+    EncodingId::ShiftJis,
+    EncodingId::Utf16Be,
+    EncodingId::Utf16Le,
+    EncodingId::Utf8,
+    EncodingId::Gb18030,
+    EncodingId::Macintosh,
+    EncodingId::Replacement,
+    EncodingId::Windows1250,
+    EncodingId::Windows1251,
+    EncodingId::Windows1252,
+    EncodingId::Windows1253,
+    EncodingId::Windows1254,
+    EncodingId::Windows1255,
+    EncodingId::Windows1256,
+    EncodingId::Windows1257,
+    EncodingId::Windows1258,
+    EncodingId::Windows874,
+    EncodingId::MacCyrillic,
+    EncodingId::UserDefined,
+];
+
+/// A compact identifier of an encoding, for storing in packed records,
+/// bit sets or FFI structs.
+///
+/// The discriminants follow the order of `Encoding::all()`.
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EncodingId {
+    /// [Big5](static.BIG5.html)
+    Big5 = 0,
+    /// [EUC-JP](static.EUC_JP.html)
+    EucJp = 1,
+    /// [EUC-KR](static.EUC_KR.html)
+    EucKr = 2,
+    /// [GBK](static.GBK.html)
+    Gbk = 3,
+    /// [IBM866](static.IBM866.html)
+    Ibm866 = 4,
+    /// [ISO-2022-JP](static.ISO_2022_JP.html)
+    Iso2022Jp = 5,
+    /// [ISO-8859-10](static.ISO_8859_10.html)
+    Iso10 = 6,
+    /// [ISO-8859-13](static.ISO_8859_13.html)
+    Iso13 = 7,
+    /// [ISO-8859-14](static.ISO_8859_14.html)
+    Iso14 = 8,
+    /// [ISO-8859-15](static.ISO_8859_15.html)
+    Iso15 = 9,
+    /// [ISO-8859-16](static.ISO_8859_16.html)
+    Iso16 = 10,
+    /// [ISO-8859-2](static.ISO_8859_2.html)
+    Iso2 = 11,
+    /// [ISO-8859-3](static.ISO_8859_3.html)
+    Iso3 = 12,
+    /// [ISO-8859-4](static.ISO_8859_4.html)
+    Iso4 = 13,
+    /// [ISO-8859-5](static.ISO_8859_5.html)
+    Iso5 = 14,
+    /// [ISO-8859-6](static.ISO_8859_6.html)
+    Iso6 = 15,
+    /// [ISO-8859-7](static.ISO_8859_7.html)
+    Iso7 = 16,
+    /// [ISO-8859-8](static.ISO_8859_8.html)
+    Iso8 = 17,
+    /// [ISO-8859-8-I](static.ISO_8859_8_I.html)
+    Iso8I = 18,
+    /// [KOI8-R](static.KOI8_R.html)
+    Koi8R = 19,
+    /// [KOI8-U](static.KOI8_U.html)
+    Koi8U = 20,
+    /// [Shift_JIS](static.SHIFT_JIS.html)
+    ShiftJis = 21,
+    /// [UTF-16BE](static.UTF_16BE.html)
+    Utf16Be = 22,
+    /// [UTF-16LE](static.UTF_16LE.html)
+    Utf16Le = 23,
+    /// [UTF-8](static.UTF_8.html)
+    Utf8 = 24,
+    /// [gb18030](static.GB18030.html)
+    Gb18030 = 25,
+    /// [macintosh](static.MACINTOSH.html)
+    Macintosh = 26,
+    /// [replacement](static.REPLACEMENT.html)
+    Replacement = 27,
+    /// [windows-1250](static.WINDOWS_1250.html)
+    Windows1250 = 28,
+    /// [windows-1251](static.WINDOWS_1251.html)
+    Windows1251 = 29,
+    /// [windows-1252](static.WINDOWS_1252.html)
+    Windows1252 = 30,
+    /// [windows-1253](static.WINDOWS_1253.html)
+    Windows1253 = 31,
+    /// [windows-1254](static.WINDOWS_1254.html)
+    Windows1254 = 32,
+    /// [windows-1255](static.WINDOWS_1255.html)
+    Windows1255 = 33,
+    /// [windows-1256](static.WINDOWS_1256.html)
+    Windows1256 = 34,
+    /// [windows-1257](static.WINDOWS_1257.html)
+    Windows1257 = 35,
+    /// [windows-1258](static.WINDOWS_1258.html)
+    Windows1258 = 36,
+    /// [windows-874](static.WINDOWS_874.html)
+    Windows874 = 37,
+    /// [x-mac-cyrillic](static.X_MAC_CYRILLIC.html)
+    MacCyrillic = 38,
+    /// [x-user-defined](static.X_USER_DEFINED.html)
+    UserDefined = 39,
+}
+
+static MIB_ENUMS_SORTED: [u16; 54] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 17, 18, 36, 37, 38, 39, 57, 81, 82, 84, 85, 104, 105, 106,
+    109, 110, 111, 112, 113, 114, 1013, 1014, 1015, 2024, 2025, 2026, 2027, 2084, 2085, 2086, 2088,
+    2101, 2109, 2250, 2251, 2252, 2253, 2254, 2255, 2256, 2257, 2258, 2259,
+];
+
+static ENCODINGS_IN_MIB_ENUM_SORT: [&Encoding; 54] = [
+    &WINDOWS_1252_INIT,
+    &WINDOWS_1252_INIT,
+    &ISO_8859_2_INIT,
+    &ISO_8859_3_INIT,
+    &ISO_8859_4_INIT,
+    &ISO_8859_5_INIT,
+    &ISO_8859_6_INIT,
+    &ISO_8859_7_INIT,
+    &ISO_8859_8_INIT,
+    &WINDOWS_1254_INIT,
+    &ISO_8859_10_INIT,
+    &SHIFT_JIS_INIT,
+    &EUC_JP_INIT,
+    &EUC_KR_INIT,
+    &REPLACEMENT_INIT,
+    &EUC_KR_INIT,
+    &ISO_2022_JP_INIT,
+    &GBK_INIT,
+    &ISO_8859_6_INIT,
+    &ISO_8859_6_INIT,
+    &ISO_8859_8_INIT,
+    &ISO_8859_8_I_INIT,
+    &REPLACEMENT_INIT,
+    &REPLACEMENT_INIT,
+    &UTF_8_INIT,
+    &ISO_8859_13_INIT,
+    &ISO_8859_14_INIT,
+    &ISO_8859_15_INIT,
+    &ISO_8859_16_INIT,
+    &GBK_INIT,
+    &GB18030_INIT,
+    &UTF_16BE_INIT,
+    &UTF_16LE_INIT,
+    &UTF_16LE_INIT,
+    &SHIFT_JIS_INIT,
+    &GBK_INIT,
+    &BIG5_INIT,
+    &MACINTOSH_INIT,
+    &KOI8_R_INIT,
+    &REPLACEMENT_INIT,
+    &IBM866_INIT,
+    &KOI8_U_INIT,
+    &BIG5_INIT,
+    &WINDOWS_874_INIT,
+    &WINDOWS_1250_INIT,
+    &WINDOWS_1251_INIT,
+    &WINDOWS_1252_INIT,
+    &WINDOWS_1253_INIT,
+    &WINDOWS_1254_INIT,
+    &WINDOWS_1255_INIT,
+    &WINDOWS_1256_INIT,
+    &WINDOWS_1257_INIT,
+    &WINDOWS_1258_INIT,
+    &WINDOWS_874_INIT,
+];
+
+static MIB_ENUMS_BY_ENCODING: [(&Encoding, u16); 37] = [
+    (&BIG5_INIT, 2026),
+    (&EUC_JP_INIT, 18),
+    (&EUC_KR_INIT, 38),
+    (&GBK_INIT, 113),
+    (&IBM866_INIT, 2086),
+    (&ISO_2022_JP_INIT, 39),
+    (&ISO_8859_10_INIT, 13),
+    (&ISO_8859_13_INIT, 109),
+    (&ISO_8859_14_INIT, 110),
+    (&ISO_8859_15_INIT, 111),
+    (&ISO_8859_16_INIT, 112),
+    (&ISO_8859_2_INIT, 5),
+    (&ISO_8859_3_INIT, 6),
+    (&ISO_8859_4_INIT, 7),
+    (&ISO_8859_5_INIT, 8),
+    (&ISO_8859_6_INIT, 9),
+    (&ISO_8859_7_INIT, 10),
+    (&ISO_8859_8_INIT, 11),
+    (&ISO_8859_8_I_INIT, 85),
+    (&KOI8_R_INIT, 2084),
+    (&KOI8_U_INIT, 2088),
+    (&SHIFT_JIS_INIT, 17),
+    (&UTF_16BE_INIT, 1013),
+    (&UTF_16LE_INIT, 1014),
+    (&UTF_8_INIT, 106),
+    (&GB18030_INIT, 114),
+    (&MACINTOSH_INIT, 2027),
+    (&WINDOWS_1250_INIT, 2250),
+    (&WINDOWS_1251_INIT, 2251),
+    (&WINDOWS_1252_INIT, 2252),
+    (&WINDOWS_1253_INIT, 2253),
+    (&WINDOWS_1254_INIT, 2254),
+    (&WINDOWS_1255_INIT, 2255),
+    (&WINDOWS_1256_INIT, 2256),
+    (&WINDOWS_1257_INIT, 2257),
+    (&WINDOWS_1258_INIT, 2258),
+    (&WINDOWS_874_INIT, 2109),
+];
+
+static CODE_PAGES_SORTED: [u16; 49] = [
+    866, 874, 932, 936, 949, 950, 951, 1200, 1201, 1250, 1251, 1252, 1253, 1254, 1255, 1256, 1257,
+    1258, 10000, 10017, 20866, 20932, 20936, 20949, 21866, 28591, 28592, 28593, 28594, 28595,
+    28596, 28597, 28598, 28599, 28600, 28601, 28603, 28604, 28605, 28606, 38598, 50220, 50221,
+    50222, 51932, 51936, 51949, 54936, 65001,
+];
+
+static ENCODINGS_IN_CODE_PAGE_SORT: [&Encoding; 49] = [
+    &IBM866_INIT,
+    &WINDOWS_874_INIT,
+    &SHIFT_JIS_INIT,
+    &GBK_INIT,
+    &EUC_KR_INIT,
+    &BIG5_INIT,
+    &BIG5_INIT,
+    &UTF_16LE_INIT,
+    &UTF_16BE_INIT,
+    &WINDOWS_1250_INIT,
+    &WINDOWS_1251_INIT,
+    &WINDOWS_1252_INIT,
+    &WINDOWS_1253_INIT,
+    &WINDOWS_1254_INIT,
+    &WINDOWS_1255_INIT,
+    &WINDOWS_1256_INIT,
+    &WINDOWS_1257_INIT,
+    &WINDOWS_1258_INIT,
+    &MACINTOSH_INIT,
+    &X_MAC_CYRILLIC_INIT,
+    &KOI8_R_INIT,
+    &EUC_JP_INIT,
+    &GBK_INIT,
+    &EUC_KR_INIT,
+    &KOI8_U_INIT,
+    &WINDOWS_1252_INIT,
+    &ISO_8859_2_INIT,
+    &ISO_8859_3_INIT,
+    &ISO_8859_4_INIT,
+    &ISO_8859_5_INIT,
+    &ISO_8859_6_INIT,
+    &ISO_8859_7_INIT,
+    &ISO_8859_8_INIT,
+    &WINDOWS_1254_INIT,
+    &ISO_8859_10_INIT,
+    &WINDOWS_874_INIT,
+    &ISO_8859_13_INIT,
+    &ISO_8859_14_INIT,
+    &ISO_8859_15_INIT,
+    &ISO_8859_16_INIT,
+    &ISO_8859_8_I_INIT,
+    &ISO_2022_JP_INIT,
+    &ISO_2022_JP_INIT,
+    &ISO_2022_JP_INIT,
+    &EUC_JP_INIT,
+    &GBK_INIT,
+    &EUC_KR_INIT,
+    &GB18030_INIT,
+    &UTF_8_INIT,
+];
+
+static CODE_PAGES_BY_ENCODING: [(&Encoding, u16); 38] = [
+    (&BIG5_INIT, 950),
+    (&EUC_JP_INIT, 20932),
+    (&EUC_KR_INIT, 949),
+    (&GBK_INIT, 936),
+    (&IBM866_INIT, 866),
+    (&ISO_2022_JP_INIT, 50221),
+    (&ISO_8859_10_INIT, 28600),
+    (&ISO_8859_13_INIT, 28603),
+    (&ISO_8859_14_INIT, 28604),
+    (&ISO_8859_15_INIT, 28605),
+    (&ISO_8859_16_INIT, 28606),
+    (&ISO_8859_2_INIT, 28592),
+    (&ISO_8859_3_INIT, 28593),
+    (&ISO_8859_4_INIT, 28594),
+    (&ISO_8859_5_INIT, 28595),
+    (&ISO_8859_6_INIT, 28596),
+    (&ISO_8859_7_INIT, 28597),
+    (&ISO_8859_8_INIT, 28598),
+    (&ISO_8859_8_I_INIT, 38598),
+    (&KOI8_R_INIT, 20866),
+    (&KOI8_U_INIT, 21866),
+    (&SHIFT_JIS_INIT, 932),
+    (&UTF_16BE_INIT, 1201),
+    (&UTF_16LE_INIT, 1200),
+    (&UTF_8_INIT, 65001),
+    (&GB18030_INIT, 54936),
+    (&MACINTOSH_INIT, 10000),
+    (&WINDOWS_1250_INIT, 1250),
+    (&WINDOWS_1251_INIT, 1251),
+    (&WINDOWS_1252_INIT, 1252),
+    (&WINDOWS_1253_INIT, 1253),
+    (&WINDOWS_1254_INIT, 1254),
+    (&WINDOWS_1255_INIT, 1255),
+    (&WINDOWS_1256_INIT, 1256),
+    (&WINDOWS_1257_INIT, 1257),
+    (&WINDOWS_1258_INIT, 1258),
+    (&WINDOWS_874_INIT, 874),
+    (&X_MAC_CYRILLIC_INIT, 10017),
+];
+
 // END GENERATED CODE
 
 /// An encoding as defined in the [Encoding Standard][1].
@@ -2635,6 +3387,7 @@ static ENCODINGS_IN_LABEL_SORT: [&'static Encoding; 219] = [
 /// C/C++).
 pub struct Encoding {
     name: &'static str,
+    id: EncodingId,
     variant: VariantEncoding,
 }
 
@@ -2795,6 +3548,141 @@ impl Encoding {
         }
     }
 
+    /// Looks up a charset name used by a platform charset registry, such as
+    /// the names known to Java, ICU or Python, e.g. `MS949`,
+    /// `ibm-943_P15A-2003` or `sjis_2004`.
+    ///
+    /// Returns the closest encoding and whether it is an exact match or
+    /// `None` if there is no reasonable match. Names that `for_label()`
+    /// maps to `REPLACEMENT` yield `None`, because the charsets they name
+    /// are real encodings that this crate can't decode.
+    ///
+    /// Names are matched ASCII case-insensitively and with underscores and
+    /// hyphens treated as equal. Labels of the Encoding Standard are
+    /// accepted, too, but labels that the Encoding Standard maps to a
+    /// superset of the charset they name, such as `iso-8859-1` or `gb2312`,
+    /// are reported as approximate matches.
+    ///
+    /// Available to Rust only.
+    pub fn for_platform_name(name: &[u8]) -> Option<LabelMatch> {
+        let trimmed = trim_label_whitespace(name);
+        let mut normalized = [0u8; LONGEST_PLATFORM_NAME_LENGTH];
+        let normalized = if trimmed.len() <= LONGEST_PLATFORM_NAME_LENGTH {
+            for (dst, &byte) in normalized.iter_mut().zip(trimmed.iter()) {
+                *dst = if byte == b'_' {
+                    b'-'
+                } else {
+                    byte.to_ascii_lowercase()
+                };
+            }
+            let normalized = &normalized[..trimmed.len()];
+            if let Ok(i) = PLATFORM_NAMES_SORTED
+                .binary_search_by(|&(probe, _, _)| probe.as_bytes().cmp(normalized))
+            {
+                let (_, label, exact) = PLATFORM_NAMES_SORTED[i];
+                let encoding = Encoding::for_label(label.as_bytes()).unwrap();
+                return Some(if exact {
+                    LabelMatch::Exact(encoding)
+                } else {
+                    LabelMatch::Approximate(encoding)
+                });
+            }
+            Some(normalized)
+        } else {
+            None
+        };
+        let encoding = match Encoding::for_label_no_replacement(trimmed) {
+            Some(encoding) => encoding,
+            None => match normalized {
+                Some(normalized) => Encoding::for_label_no_replacement(normalized)?,
+                None => return None,
+            },
+        };
+        Some(LabelMatch::Exact(encoding))
+    }
+
+    /// Returns the encoding that corresponds to a MySQL character set name,
+    /// such as the charset of a column, or `None` if MySQL's character set
+    /// has no counterpart in the Encoding Standard.
+    ///
+    /// The name is matched ASCII case-insensitively. Note that MySQL's
+    /// `latin1` is windows-1252 and its `utf16` is UTF-16BE. `binary` and
+    /// character sets such as `utf32`, `ucs2` or `armscii8` yield `None`.
+    ///
+    /// Available to Rust only.
+    pub fn for_mysql_charset(name: &[u8]) -> Option<&'static Encoding> {
+        for_database_name(&MYSQL_CHARSETS_SORTED[..], name)
+    }
+
+    /// Returns the encoding that corresponds to a PostgreSQL encoding name,
+    /// such as the value of `client_encoding` or `server_encoding`, or
+    /// `None` if PostgreSQL's encoding has no counterpart in the Encoding
+    /// Standard.
+    ///
+    /// The name is matched the way PostgreSQL matches it: ASCII
+    /// case-insensitively and ignoring non-alphanumeric characters, so
+    /// `EUC_JIS_2004` and `eucjis2004` are equal. `SQL_ASCII`, which doesn't
+    /// denote an encoding, and encodings such as `EUC_TW`, `JOHAB` or
+    /// `MULE_INTERNAL` yield `None`.
+    ///
+    /// Available to Rust only.
+    pub fn for_postgresql_encoding(name: &[u8]) -> Option<&'static Encoding> {
+        for_database_name(&POSTGRESQL_ENCODINGS_SORTED[..], name)
+    }
+
+    /// Returns the fallback encoding that web browsers use for legacy content
+    /// that doesn't declare its encoding when the user's locale is `locale`.
+    ///
+    /// `locale` is a BCP 47 language tag, such as `ja` or `zh-TW`, and is
+    /// matched ASCII case-insensitively with `_` accepted as a separator.
+    /// Chinese maps to `BIG5` if the tag has the `Hant` script or the `TW`,
+    /// `HK` or `MO` region and to `GBK` otherwise. Languages without a
+    /// specific fallback, and malformed tags, map to `WINDOWS_1252`.
+    ///
+    /// The fallbacks are Gecko's, which differ from the suggestions of the
+    /// HTML Standard where Gecko's choice is more compatible with the Web,
+    /// e.g. the Standard suggests `GB18030` instead of `GBK` for Chinese.
+    ///
+    /// Available to Rust only.
+    pub fn fallback_for_locale(locale: &str) -> &'static Encoding {
+        let locale = locale.trim().to_ascii_lowercase().replace('_', "-");
+        let mut subtags = locale.split('-');
+        let language = subtags.next().unwrap_or("");
+        if language == "zh" {
+            if subtags.any(|subtag| ["hant", "tw", "hk", "mo"].contains(&subtag)) {
+                return BIG5;
+            }
+            return GBK;
+        }
+        match LOCALE_FALLBACKS.binary_search_by(|&(probe, _)| probe.cmp(language)) {
+            Ok(i) => LOCALE_FALLBACKS[i].1,
+            Err(_) => WINDOWS_1252,
+        }
+    }
+
+    /// Returns the fallback encoding that web browsers use for legacy content
+    /// that doesn't declare its encoding when the content comes from the
+    /// top-level domain `tld` or `None` if the domain doesn't indicate a
+    /// fallback, in which case the fallback for the user's locale applies.
+    ///
+    /// `tld` is matched ASCII case-insensitively and may have a leading dot.
+    /// A host name, such as `www.example.co.jp`, is accepted, too, in which
+    /// case its last label is used. Country code domains of countries whose
+    /// main language has a specific fallback in `fallback_for_locale()` map
+    /// to that fallback, e.g. `jp` to `SHIFT_JIS` and `ru` to
+    /// `WINDOWS_1251`. Generic domains, such as `com`, yield `None`.
+    ///
+    /// Available to Rust only.
+    pub fn fallback_for_tld(tld: &str) -> Option<&'static Encoding> {
+        #[allow(deprecated)]
+        let tld = tld.trim().trim_right_matches('.');
+        let tld = tld.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+        match TLD_FALLBACKS.binary_search_by(|&(probe, _)| probe.cmp(&tld[..])) {
+            Ok(i) => Some(TLD_FALLBACKS[i].1),
+            Err(_) => None,
+        }
+    }
+
     /// Performs non-incremental BOM sniffing.
     ///
     /// The argument must either be a buffer representing the entire input
@@ -2819,6 +3707,38 @@ impl Encoding {
         }
     }
 
+    /// Returns the encoding that corresponds to a Windows code page number
+    /// or `None` if no encoding in the Encoding Standard corresponds to
+    /// the code page.
+    ///
+    /// Besides the code page that `windows_code_page()` returns for each
+    /// encoding, this accepts code pages that Windows treats as aliases of
+    /// or that are subsets of an encoding. For example, 28591 (ISO-8859-1)
+    /// maps to `WINDOWS_1252` and 50220 and 50222 map to `ISO_2022_JP`.
+    ///
+    /// Available to Rust only.
+    pub fn for_windows_code_page(code_page: u16) -> Option<&'static Encoding> {
+        match CODE_PAGES_SORTED.binary_search(&code_page) {
+            Ok(i) => Some(ENCODINGS_IN_CODE_PAGE_SORT[i]),
+            Err(_) => None,
+        }
+    }
+
+    /// Returns the encoding that corresponds to an IANA charset MIBenum or
+    /// `None` if the MIBenum isn't known to correspond to an encoding.
+    ///
+    /// The charset that the MIBenum is registered for is resolved as a
+    /// label as in `for_label()`. For example, 4 (ISO-8859-1) maps to
+    /// `WINDOWS_1252` and 37 (ISO-2022-KR) maps to `REPLACEMENT`.
+    ///
+    /// Available to Rust only.
+    pub fn for_mib_enum(mib_enum: u16) -> Option<&'static Encoding> {
+        match MIB_ENUMS_SORTED.binary_search(&mib_enum) {
+            Ok(i) => Some(ENCODINGS_IN_MIB_ENUM_SORT[i]),
+            Err(_) => None,
+        }
+    }
+
     /// Returns the name of this encoding.
     ///
     /// This name is appropriate to return as-is from the DOM
@@ -2830,29 +3750,241 @@ impl Encoding {
         self.name
     }
 
-    /// Checks whether the _output encoding_ of this encoding can encode every
-    /// `char`. (Only true if the output encoding is UTF-8.)
+    /// Returns all encodings of the Encoding Standard ordered by name,
+    /// comparing the names byte by byte.
     ///
-    /// Available via the C wrapper.
-    #[inline]
-    pub fn can_encode_everything(&'static self) -> bool {
-        self.output_encoding() == UTF_8
-    }
-
-    /// Checks whether the bytes 0x00...0x7F map exclusively to the characters
-    /// U+0000...U+007F and vice versa.
+    /// This includes the replacement encoding, which no label is meant to
+    /// select in user interfaces, and UTF-16BE and UTF-16LE, which can't be
+    /// used for encoding.
     ///
-    /// Available via the C wrapper.
-    #[inline]
-    pub fn is_ascii_compatible(&'static self) -> bool {
-        !(self == REPLACEMENT || self == UTF_16BE || self == UTF_16LE || self == ISO_2022_JP)
+    /// Available to Rust only.
+    pub fn all() -> &'static [&'static Encoding] {
+        &ENCODINGS[..]
     }
 
-    /// Checks whether the bytes 0x00...0x7F map mostly to the characters
-    /// U+0000...U+007F and vice versa.
-    #[inline]
-    fn is_potentially_borrowable(&'static self) -> bool {
-        !(self == REPLACEMENT || self == UTF_16BE || self == UTF_16LE)
+    /// Returns the compact identifier of this encoding.
+    ///
+    /// Available to Rust only.
+    pub fn id(&'static self) -> EncodingId {
+        self.id
+    }
+
+    /// Returns an iterator over all the labels that `for_label()` maps to
+    /// this encoding in no particular order.
+    ///
+    /// The labels are lowercase.
+    ///
+    /// Available to Rust only.
+    pub fn labels(&'static self) -> Labels {
+        Labels {
+            encoding: self,
+            index: 0,
+        }
+    }
+
+    /// Suggests up to `max_suggestions` encodings for a label that
+    /// `for_label()` may have rejected, e.g. a misspelled one, ordered from
+    /// the most to the least likely.
+    ///
+    /// The label is compared with every label of the Encoding Standard
+    /// ASCII case-insensitively, ignoring separators (whitespace, `-`, `_`,
+    /// `.` and `:`) and leading zeros of numbers, by edit distance. The
+    /// confidence of a suggestion is 1.0 if the label matches exactly after
+    /// this normalization and decreases with the edit distance relative to
+    /// the length of the longer label. Suggestions with confidence below 0.5
+    /// aren't returned. Each encoding is suggested at most once, with its
+    /// closest label. The replacement encoding is never suggested.
+    ///
+    /// Available to Rust only.
+    pub fn suggest_labels(label: &[u8], max_suggestions: usize) -> Vec<LabelSuggestion> {
+        let input = normalize_label_for_suggestion(label);
+        let mut suggestions: Vec<LabelSuggestion> = Vec::new();
+        for (candidate, &encoding) in LABELS_SORTED.iter().zip(ENCODINGS_IN_LABEL_SORT.iter()) {
+            if encoding == REPLACEMENT {
+                continue;
+            }
+            let normalized = normalize_label_for_suggestion(candidate.as_bytes());
+            let longer = std::cmp::max(input.len(), normalized.len());
+            if longer == 0 {
+                continue;
+            }
+            let distance = edit_distance(&input, &normalized);
+            let confidence = 1.0 - (distance as f32 / longer as f32);
+            if confidence < 0.5 {
+                continue;
+            }
+            match suggestions.iter().position(|s| s.encoding == encoding) {
+                Some(i) => {
+                    if suggestions[i].confidence < confidence {
+                        suggestions[i].label = candidate;
+                        suggestions[i].confidence = confidence;
+                    }
+                }
+                None => suggestions.push(LabelSuggestion {
+                    label: candidate,
+                    encoding,
+                    confidence,
+                }),
+            }
+        }
+        suggestions.sort_by(|a, b| {
+            b.confidence
+                .partial_cmp(&a.confidence)
+                .unwrap()
+                .then_with(|| a.encoding.name().cmp(b.encoding.name()))
+        });
+        suggestions.truncate(max_suggestions);
+        suggestions
+    }
+
+    /// Returns the Windows code page number of this encoding or `None` for
+    /// the replacement and x-user-defined encodings, which have no Windows
+    /// code page.
+    ///
+    /// Available to Rust only.
+    pub fn windows_code_page(&'static self) -> Option<u16> {
+        CODE_PAGES_BY_ENCODING
+            .iter()
+            .find(|&&(encoding, _)| encoding == self)
+            .map(|&(_, code_page)| code_page)
+    }
+
+    /// Returns the IANA charset MIBenum registered for the name of this
+    /// encoding or `None` if the name isn't registered with a MIBenum.
+    ///
+    /// Available to Rust only.
+    pub fn mib_enum(&'static self) -> Option<u16> {
+        MIB_ENUMS_BY_ENCODING
+            .iter()
+            .find(|&&(encoding, _)| encoding == self)
+            .map(|&(_, mib_enum)| mib_enum)
+    }
+
+    /// Checks whether the _output encoding_ of this encoding can encode every
+    /// `char`. (Only true if the output encoding is UTF-8.)
+    ///
+    /// Available via the C wrapper.
+    #[inline]
+    pub fn can_encode_everything(&'static self) -> bool {
+        self.output_encoding() == UTF_8
+    }
+
+    /// Checks whether the bytes 0x00...0x7F map exclusively to the characters
+    /// U+0000...U+007F and vice versa.
+    ///
+    /// Available via the C wrapper.
+    #[inline]
+    pub fn is_ascii_compatible(&'static self) -> bool {
+        !(self == REPLACEMENT || self == UTF_16BE || self == UTF_16LE || self == ISO_2022_JP)
+    }
+
+    /// Checks whether each byte decodes to one character on its own, i.e.
+    /// whether this is one of the legacy single-byte encodings or
+    /// x-user-defined.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn is_single_byte(&'static self) -> bool {
+        match self.variant {
+            VariantEncoding::SingleByte(_) => true,
+            _ => self == X_USER_DEFINED,
+        }
+    }
+
+    /// Checks whether the meaning of bytes depends on earlier escape
+    /// sequences, so that a decoder or an encoder carries state from one
+    /// character to the next. (Only true for ISO-2022-JP.)
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn is_stateful(&'static self) -> bool {
+        self == ISO_2022_JP
+    }
+
+    /// Checks whether this is one of the legacy Chinese, Japanese or Korean
+    /// multi-byte encodings.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn is_cjk(&'static self) -> bool {
+        self == GBK
+            || self == GB18030
+            || self == BIG5
+            || self == EUC_JP
+            || self == ISO_2022_JP
+            || self == SHIFT_JIS
+            || self == EUC_KR
+    }
+
+    /// Returns the maximum number of bytes that encoding a single character
+    /// with `encode()` or an `Encoder` produces, not counting the escape
+    /// sequences of ISO-2022-JP.
+    ///
+    /// Like encoding, this applies to the _output encoding_, so the value
+    /// for UTF-16BE, UTF-16LE and replacement is the value for UTF-8.
+    ///
+    /// Available to Rust only.
+    pub fn max_bytes_per_char(&'static self) -> usize {
+        match self.output_encoding().variant {
+            VariantEncoding::SingleByte(_) | VariantEncoding::UserDefined => 1,
+            VariantEncoding::Utf8 | VariantEncoding::Gb18030 => 4,
+            _ => 2,
+        }
+    }
+
+    /// Checks whether this is UTF-16BE or UTF-16LE.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn is_utf16(&'static self) -> bool {
+        self == UTF_16BE || self == UTF_16LE
+    }
+
+    /// Checks whether this encoding has a byte order mark that the BOM
+    /// sniffing of `decode()` and `new_decoder()` recognizes. (Only true for
+    /// UTF-8, UTF-16BE and UTF-16LE.)
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn has_bom(&'static self) -> bool {
+        self == UTF_8 || self.is_utf16()
+    }
+
+    /// Checks whether every byte sequence that `other` decodes without
+    /// malformed sequences decodes to the same characters with this
+    /// encoding.
+    ///
+    /// Every encoding is a superset of itself. GBK and gb18030 share a
+    /// decoder, so each one is a superset of the other, and ISO-8859-8 and
+    /// ISO-8859-8-I share a mapping. Otherwise, only single-byte encodings
+    /// whose mappings agree wherever `other` has one can be supersets. The
+    /// replacement encoding is not a subset of any other encoding even
+    /// though it decodes no non-empty byte sequence without errors.
+    ///
+    /// Available to Rust only.
+    pub fn is_superset_of(&'static self, other: &'static Encoding) -> bool {
+        if self == other {
+            return true;
+        }
+        match (&self.variant, &other.variant) {
+            (&VariantEncoding::SingleByte(data), &VariantEncoding::SingleByte(other_data)) => {
+                data.iter()
+                    .zip(other_data.iter())
+                    .all(|(&code_point, &other_code_point)| {
+                        other_code_point == 0 || code_point == other_code_point
+                    })
+            }
+            (&VariantEncoding::Gbk, &VariantEncoding::Gb18030)
+            | (&VariantEncoding::Gb18030, &VariantEncoding::Gbk) => true,
+            _ => false,
+        }
+    }
+
+    /// Checks whether the bytes 0x00...0x7F map mostly to the characters
+    /// U+0000...U+007F and vice versa.
+    #[inline]
+    fn is_potentially_borrowable(&'static self) -> bool {
+        !(self == REPLACEMENT || self == UTF_16BE || self == UTF_16LE)
     }
 
     /// Returns the _output encoding_ of this encoding. This is UTF-8 for
@@ -2912,6 +4044,274 @@ impl Encoding {
         (cow, encoding, had_errors)
     }
 
+    /// Decode complete input to `Cow<'a, str>` _with BOM sniffing_ and with
+    /// malformed sequences replaced with the REPLACEMENT CHARACTER when the
+    /// entire input is available as a single buffer (i.e. the end of the
+    /// buffer marks the end of the stream) recording the location of each
+    /// replacement.
+    ///
+    /// This method behaves the same as `decode()`, except instead of a
+    /// boolean indicating whether there were malformed sequences, the third
+    /// item in the returned tuple is a vector of [`MalformedSpan`][1]s, one
+    /// for each malformed sequence. The input ranges of the spans are indices
+    /// into `bytes` (i.e. a BOM counts). The output ranges of the spans are
+    /// indices into the returned string.
+    ///
+    /// [1]: struct.MalformedSpan.html
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input. Use `new_decoder()`
+    /// and `Decoder::decode_to_string_with_malformed_spans()` when decoding
+    /// segmented input.
+    ///
+    /// When there are no malformed sequences, this method performs the same
+    /// allocations as `decode()` and the vector is empty without having
+    /// allocated. When there are malformed sequences, the input is decoded
+    /// a second time in order to locate them.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn decode_with_malformed_spans<'a>(
+        &'static self,
+        bytes: &'a [u8],
+    ) -> (Cow<'a, str>, &'static Encoding, Vec<MalformedSpan>) {
+        let (cow, encoding, had_errors) = self.decode(bytes);
+        if !had_errors {
+            return (cow, encoding, Vec::new());
+        }
+        let mut decoder = self.new_decoder();
+        let mut string =
+            String::with_capacity(decoder.max_utf8_buffer_length(bytes.len()).unwrap());
+        let mut spans = Vec::new();
+        let (result, read, _) =
+            decoder.decode_to_string_with_malformed_spans(bytes, &mut string, true, &mut spans);
+        debug_assert_eq!(result, CoderResult::InputEmpty);
+        debug_assert_eq!(read, bytes.len());
+        debug_assert_eq!(decoder.encoding(), encoding);
+        debug_assert_eq!(&string[..], &cow[..]);
+        (Cow::Owned(string), encoding, spans)
+    }
+
+    /// Decode complete input to `Cow<'a, str>` _with BOM sniffing_ and with
+    /// malformed sequences handled by a caller-supplied function when the
+    /// entire input is available as a single buffer (i.e. the end of the
+    /// buffer marks the end of the stream).
+    ///
+    /// This method behaves the same as `decode()`, except instead of
+    /// appending the REPLACEMENT CHARACTER for each malformed sequence,
+    /// `handler` is called with the bytes of the malformed sequence and the
+    /// output decoded so far. The handler may append anything to the output
+    /// (or nothing). Decoding then continues after the malformed sequence.
+    ///
+    /// The third item in the returned tuple indicates whether there were
+    /// malformed sequences (i.e. whether `handler` was called).
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input. When decoding
+    /// segmented input, use `new_decoder()` and
+    /// `Decoder::decode_to_string_with_malformed_handler()` instead.
+    ///
+    /// A borrow is performed if decoding UTF-8 and the input is valid UTF-8,
+    /// if decoding an ASCII-compatible encoding and the input is ASCII-only,
+    /// or when decoding ISO-2022-JP and the input is entirely in the ASCII
+    /// state without state transitions. Otherwise, the backing buffer of the
+    /// `String` is heap-allocated and grown as needed.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn decode_with_malformed_handler<'a, F>(
+        &'static self,
+        bytes: &'a [u8],
+        mut handler: F,
+    ) -> (Cow<'a, str>, &'static Encoding, bool)
+    where
+        F: FnMut(&[u8], &mut String),
+    {
+        let (encoding, bytes) = match Encoding::for_bom(bytes) {
+            Some((encoding, bom_length)) => (encoding, &bytes[bom_length..]),
+            None => (self, bytes),
+        };
+        let valid_up_to = if encoding.is_potentially_borrowable() {
+            let valid_up_to = if encoding == UTF_8 {
+                utf8_valid_up_to(bytes)
+            } else if encoding == ISO_2022_JP {
+                iso_2022_jp_ascii_valid_up_to(bytes)
+            } else {
+                ascii_valid_up_to(bytes)
+            };
+            if valid_up_to == bytes.len() {
+                let str: &str = unsafe { std::str::from_utf8_unchecked(bytes) };
+                return (Cow::Borrowed(str), encoding, false);
+            }
+            valid_up_to
+        } else {
+            0
+        };
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut string = String::with_capacity(
+            checked_add(
+                valid_up_to,
+                decoder.max_utf8_buffer_length_without_replacement(bytes.len() - valid_up_to),
+            ).unwrap(),
+        );
+        unsafe {
+            let vec = string.as_mut_vec();
+            vec.set_len(valid_up_to);
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), vec.as_mut_ptr(), valid_up_to);
+        }
+        let mut total_read = valid_up_to;
+        let mut had_errors = false;
+        loop {
+            let (result, read) = decoder.decode_to_string_without_replacement(
+                &bytes[total_read..],
+                &mut string,
+                true,
+            );
+            total_read += read;
+            match result {
+                DecoderResult::InputEmpty => {
+                    debug_assert_eq!(total_read, bytes.len());
+                    return (Cow::Owned(string), encoding, had_errors);
+                }
+                DecoderResult::OutputFull => {
+                    let needed = decoder
+                        .max_utf8_buffer_length_without_replacement(bytes.len() - total_read);
+                    string.reserve(needed.unwrap());
+                }
+                DecoderResult::Malformed(malformed, consumed_after) => {
+                    had_errors = true;
+                    let end = total_read - consumed_after as usize;
+                    handler(&bytes[end - malformed as usize..end], &mut string);
+                }
+            }
+        }
+    }
+
+    /// Decode complete input to `Cow<'a, str>` _with BOM sniffing_ and with
+    /// malformed sequences handled according to a [`MalformedPolicy`][1] when
+    /// the entire input is available as a single buffer (i.e. the end of the
+    /// buffer marks the end of the stream).
+    ///
+    /// [1]: enum.MalformedPolicy.html
+    ///
+    /// This is a convenience wrapper for `decode_with_malformed_handler()`.
+    /// Calling this method with `MalformedPolicy::Replace` has the same
+    /// result as calling `decode()`.
+    ///
+    /// The third item in the returned tuple indicates whether there were
+    /// malformed sequences.
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn decode_with_malformed_policy<'a>(
+        &'static self,
+        bytes: &'a [u8],
+        policy: MalformedPolicy,
+    ) -> (Cow<'a, str>, &'static Encoding, bool) {
+        self.decode_with_malformed_handler(bytes, |malformed, dst| {
+            policy.handle(malformed, dst)
+        })
+    }
+
+    /// Decode complete input to UTF-16 _with BOM sniffing_ and with each byte
+    /// of malformed sequences escaped as an unpaired low surrogate when the
+    /// entire input is available as a single buffer (i.e. the end of the
+    /// buffer marks the end of the stream).
+    ///
+    /// This is similar to the "surrogateescape" error handler of Python:
+    /// Each byte of a malformed sequence becomes the unpaired surrogate whose
+    /// value is 0xDC00 plus the byte value. Valid input never decodes to an
+    /// unpaired surrogate, so `Encoder::encode_from_utf16_with_surrogate_escapes()`
+    /// can turn the escapes back into the original bytes. Since `str` cannot
+    /// hold unpaired surrogates, this mode is available only when decoding to
+    /// UTF-16.
+    ///
+    /// The second item in the returned tuple is the encoding that was actually
+    /// used (which may differ from this encoding thanks to BOM sniffing).
+    ///
+    /// The third item in the returned tuple indicates whether there were
+    /// malformed sequences (that were escaped).
+    ///
+    /// Re-encoding the output with the output encoding reproduces the input
+    /// byte for byte only if the valid parts of the input round-trip, too.
+    /// This is not the case for ISO-2022-JP, for UTF-16BE and UTF-16LE (whose
+    /// output encoding is UTF-8) or for byte sequences that the Encoding
+    /// Standard decodes but does not encode (e.g. duplicate mappings in the
+    /// CJK encodings). The BOM, if any, is not part of the output.
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input. When decoding
+    /// segmented input, use `new_decoder()` and
+    /// `Decoder::decode_to_utf16_with_surrogate_escapes()` instead.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn decode_to_utf16_with_surrogate_escapes(
+        &'static self,
+        bytes: &[u8],
+    ) -> (Vec<u16>, &'static Encoding, bool) {
+        let (encoding, bytes) = match Encoding::for_bom(bytes) {
+            Some((encoding, bom_length)) => (encoding, &bytes[bom_length..]),
+            None => (self, bytes),
+        };
+        let mut decoder = encoding.new_decoder_without_bom_handling();
+        let mut vec: Vec<u16> =
+            Vec::with_capacity(decoder.max_utf16_buffer_length(bytes.len()).unwrap());
+        let mut total_read = 0usize;
+        let mut had_errors = false;
+        loop {
+            let (result, read) = unsafe {
+                let old_len = vec.len();
+                let capacity = vec.capacity();
+                vec.set_len(capacity);
+                let (result, read, written) = decoder.decode_to_utf16_without_replacement(
+                    &bytes[total_read..],
+                    &mut vec[old_len..],
+                    true,
+                );
+                vec.set_len(old_len + written);
+                (result, read)
+            };
+            total_read += read;
+            match result {
+                DecoderResult::InputEmpty => {
+                    debug_assert_eq!(total_read, bytes.len());
+                    return (vec, encoding, had_errors);
+                }
+                DecoderResult::OutputFull => {
+                    let needed = decoder.max_utf16_buffer_length(bytes.len() - total_read);
+                    vec.reserve(needed.unwrap());
+                }
+                DecoderResult::Malformed(malformed, consumed_after) => {
+                    had_errors = true;
+                    let end = total_read - consumed_after as usize;
+                    for &b in &bytes[end - malformed as usize..end] {
+                        vec.push(0xDC00u16 | u16::from(b));
+                    }
+                }
+            }
+        }
+    }
+
     /// Decode complete input to `Cow<'a, str>` _with BOM removal_ and with
     /// malformed sequences replaced with the REPLACEMENT CHARACTER when the
     /// entire input is available as a single buffer (i.e. the end of the
@@ -3091,37 +4491,82 @@ impl Encoding {
         &'static self,
         bytes: &'a [u8],
     ) -> Option<Cow<'a, str>> {
-        if self == UTF_8 {
-            let valid_up_to = utf8_valid_up_to(bytes);
-            if valid_up_to == bytes.len() {
-                let str: &str = unsafe { std::mem::transmute(bytes) };
-                return Some(Cow::Borrowed(str));
-            }
-            return None;
-        }
-        let (mut decoder, mut string, input) = if self.is_potentially_borrowable() {
-            let valid_up_to = if self == ISO_2022_JP {
-                iso_2022_jp_ascii_valid_up_to(bytes)
-            } else {
-                ascii_valid_up_to(bytes)
-            };
-            if valid_up_to == bytes.len() {
-                let str: &str = unsafe { std::mem::transmute(bytes) };
-                return Some(Cow::Borrowed(str));
-            }
-            let decoder = self.new_decoder_without_bom_handling();
-            let mut string = String::with_capacity(
-                checked_add(
-                    valid_up_to,
-                    decoder.max_utf8_buffer_length_without_replacement(bytes.len() - valid_up_to),
-                ).unwrap(),
-            );
+        self.try_decode_without_bom_handling(bytes).ok()
+    }
+
+    /// Decode complete input to `Cow<'a, str>` _without BOM handling_ and
+    /// _with malformed sequences treated as fatal_ when the entire input is
+    /// available as a single buffer (i.e. the end of the buffer marks the end
+    /// of the stream) reporting the location of the first malformed sequence.
+    ///
+    /// This method behaves the same as
+    /// `decode_without_bom_handling_and_without_replacement()`, except
+    /// instead of returning `None` upon a malformed sequence, this method
+    /// returns a [`DecodeError`][1] that tells where in `bytes` the first
+    /// malformed sequence was.
+    ///
+    /// [1]: struct.DecodeError.html
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input. Use
+    /// `new_decoder_without_bom_handling()` when decoding segmented input.
+    ///
+    /// This method performs a single heap allocation for the backing
+    /// buffer of the `String` when unable to borrow. A borrow is performed if
+    /// decoding UTF-8 and the input is valid UTF-8, if decoding an
+    /// ASCII-compatible encoding and the input is ASCII-only, or when decoding
+    /// ISO-2022-JP and the input is entirely in the ASCII state without state
+    /// transitions.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn try_decode_without_bom_handling<'a>(
+        &'static self,
+        bytes: &'a [u8],
+    ) -> Result<Cow<'a, str>, DecodeError> {
+        if self == UTF_8 {
+            let valid_up_to = utf8_valid_up_to(bytes);
+            if valid_up_to == bytes.len() {
+                let str: &str = unsafe { std::mem::transmute(bytes) };
+                return Ok(Cow::Borrowed(str));
+            }
+            // The standard library uses the same definition of the length
+            // of a malformed sequence as the Encoding Standard, and it stops
+            // right away, since the error is at the start of the slice.
+            let tail = &bytes[valid_up_to..];
+            let length = match std::str::from_utf8(tail) {
+                Err(e) => e.error_len().unwrap_or(tail.len()),
+                Ok(_) => unreachable!(),
+            };
+            return Err(DecodeError::new(self, bytes, valid_up_to, length));
+        }
+        let (mut decoder, mut string, offset) = if self.is_potentially_borrowable() {
+            let valid_up_to = if self == ISO_2022_JP {
+                iso_2022_jp_ascii_valid_up_to(bytes)
+            } else {
+                ascii_valid_up_to(bytes)
+            };
+            if valid_up_to == bytes.len() {
+                let str: &str = unsafe { std::mem::transmute(bytes) };
+                return Ok(Cow::Borrowed(str));
+            }
+            let decoder = self.new_decoder_without_bom_handling();
+            let mut string = String::with_capacity(
+                checked_add(
+                    valid_up_to,
+                    decoder.max_utf8_buffer_length_without_replacement(bytes.len() - valid_up_to),
+                ).unwrap(),
+            );
             unsafe {
                 let vec = string.as_mut_vec();
                 vec.set_len(valid_up_to);
                 std::ptr::copy_nonoverlapping(bytes.as_ptr(), vec.as_mut_ptr(), valid_up_to);
             }
-            (decoder, string, &bytes[valid_up_to..])
+            (decoder, string, valid_up_to)
         } else {
             let decoder = self.new_decoder_without_bom_handling();
             let string = String::with_capacity(
@@ -3129,15 +4574,22 @@ impl Encoding {
                     .max_utf8_buffer_length_without_replacement(bytes.len())
                     .unwrap(),
             );
-            (decoder, string, bytes)
+            (decoder, string, 0)
         };
+        let input = &bytes[offset..];
         let (result, read) = decoder.decode_to_string_without_replacement(input, &mut string, true);
         match result {
             DecoderResult::InputEmpty => {
                 debug_assert_eq!(read, input.len());
-                Some(Cow::Owned(string))
+                Ok(Cow::Owned(string))
+            }
+            DecoderResult::Malformed(malformed, consumed_after) => {
+                // The whole input was in one buffer, so the malformed bytes
+                // are the ones right before the bytes consumed after them.
+                let end = offset + read - consumed_after as usize;
+                let length = malformed as usize;
+                Err(DecodeError::new(self, bytes, end - length, length))
             }
-            DecoderResult::Malformed(_, _) => None,
             DecoderResult::OutputFull => unreachable!(),
         }
     }
@@ -3235,6 +4687,225 @@ impl Encoding {
         }
     }
 
+    /// Encode complete input to `Cow<'a, [u8]>` with unmappable characters
+    /// replaced with text supplied by `handler` when the entire input is
+    /// available as a single buffer (i.e. the end of the buffer marks the
+    /// end of the stream).
+    ///
+    /// This method behaves the same as `encode()`, except unmappable
+    /// characters are replaced as documented for
+    /// `Encoder::encode_from_utf8_with_unmappable_handler()` instead of with
+    /// numeric character references.
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input. Use `new_encoder()`
+    /// when encoding segmented output.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn encode_with_unmappable_handler<'a, F>(
+        &'static self,
+        string: &'a str,
+        mut handler: F,
+    ) -> (Cow<'a, [u8]>, &'static Encoding, bool)
+    where
+        F: FnMut(char, &mut String),
+    {
+        let output_encoding = self.output_encoding();
+        if output_encoding == UTF_8 {
+            return (Cow::Borrowed(string.as_bytes()), output_encoding, false);
+        }
+        debug_assert!(output_encoding.is_potentially_borrowable());
+        let bytes = string.as_bytes();
+        let valid_up_to = if output_encoding == ISO_2022_JP {
+            iso_2022_jp_ascii_valid_up_to(bytes)
+        } else {
+            ascii_valid_up_to(bytes)
+        };
+        if valid_up_to == bytes.len() {
+            return (Cow::Borrowed(bytes), output_encoding, false);
+        }
+        let mut encoder = output_encoding.new_encoder();
+        let mut vec: Vec<u8> = Vec::with_capacity(
+            (checked_add(
+                valid_up_to,
+                encoder.max_buffer_length_from_utf8_without_replacement(string.len() - valid_up_to),
+            )).unwrap()
+                .next_power_of_two(),
+        );
+        vec.extend_from_slice(&bytes[..valid_up_to]);
+        let mut total_read = valid_up_to;
+        let mut total_had_errors = false;
+        loop {
+            let (result, read, had_errors) = unsafe {
+                let old_len = vec.len();
+                let capacity = vec.capacity();
+                vec.set_len(capacity);
+                let (result, read, written, had_errors) = encoder
+                    .encode_from_utf8_with_unmappable_handler(
+                        &string[total_read..],
+                        &mut vec[old_len..],
+                        true,
+                        &mut handler,
+                    );
+                vec.set_len(old_len + written);
+                (result, read, had_errors)
+            };
+            total_read += read;
+            total_had_errors |= had_errors;
+            match result {
+                CoderResult::InputEmpty => {
+                    debug_assert_eq!(total_read, string.len());
+                    return (Cow::Owned(vec), output_encoding, total_had_errors);
+                }
+                CoderResult::OutputFull => {
+                    // The replacement may not fit even when the rest of the
+                    // input would, so at least double the capacity.
+                    let needed = encoder
+                        .max_buffer_length_from_utf8_without_replacement(string.len() - total_read);
+                    let rounded = (checked_add(vec.capacity(), needed))
+                        .unwrap()
+                        .next_power_of_two();
+                    let additional = rounded - vec.len();
+                    vec.reserve_exact(additional);
+                }
+            }
+        }
+    }
+
+    /// Encode complete input to `Cow<'a, [u8]>` with unmappable characters
+    /// replaced according to an [`UnmappablePolicy`][1] when the entire input
+    /// is available as a single buffer (i.e. the end of the buffer marks the
+    /// end of the stream).
+    ///
+    /// [1]: enum.UnmappablePolicy.html
+    ///
+    /// This is a convenience wrapper for `encode_with_unmappable_handler()`.
+    /// Calling this method with `UnmappablePolicy::Ncr` has the same result
+    /// as calling `encode()`.
+    ///
+    /// _Note:_ It is wrong to use this when the input buffer represents only
+    /// a segment of the input instead of the whole input. Use `new_encoder()`
+    /// when encoding segmented output.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn encode_with_unmappable_policy<'a>(
+        &'static self,
+        string: &'a str,
+        policy: UnmappablePolicy,
+    ) -> (Cow<'a, [u8]>, &'static Encoding, bool) {
+        self.encode_with_unmappable_handler(string, |unmappable, dst| {
+            policy.handle(unmappable, dst)
+        })
+    }
+
+    /// Checks whether `encode()` can encode `string` without replacing any
+    /// unmappable characters.
+    ///
+    /// This method doesn't allocate.
+    ///
+    /// Available to Rust only.
+    pub fn can_encode(&'static self, string: &str) -> bool {
+        self.encodable_up_to(string) == string.len()
+    }
+
+    /// Returns the index of the first unmappable character in `string` or
+    /// `string.len()` if `encode()` can encode `string` without replacing
+    /// any unmappable characters.
+    ///
+    /// This method doesn't allocate.
+    ///
+    /// Available to Rust only.
+    pub fn encodable_up_to(&'static self, string: &str) -> usize {
+        match self.unmappables(string).next() {
+            Some((index, _)) => index,
+            None => string.len(),
+        }
+    }
+
+    /// Returns an iterator over the characters of `string` that `encode()`
+    /// would replace with numeric character references together with their
+    /// indices in `string`.
+    ///
+    /// The iterator doesn't allocate.
+    ///
+    /// Available to Rust only.
+    pub fn unmappables<'a>(&'static self, string: &'a str) -> Unmappables<'a> {
+        let output_encoding = self.output_encoding();
+        if output_encoding == UTF_8 {
+            return Unmappables {
+                variant: None,
+                string,
+                position: string.len(),
+            };
+        }
+        let bytes = string.as_bytes();
+        let valid_up_to = if output_encoding == ISO_2022_JP {
+            iso_2022_jp_ascii_valid_up_to(bytes)
+        } else {
+            ascii_valid_up_to(bytes)
+        };
+        Unmappables {
+            variant: Some(output_encoding.new_encoder().variant),
+            string,
+            position: valid_up_to,
+        }
+    }
+
+    /// Returns the output encoding of the first encoding in `candidates`
+    /// that can encode `string` without replacing any unmappable characters
+    /// or `None` if there isn't one.
+    ///
+    /// The candidates are tried in order, so they should be listed from the
+    /// most preferred to the least preferred, e.g. `[WINDOWS_1252,
+    /// ISO_2022_JP, UTF_8]`.
+    ///
+    /// This method doesn't allocate.
+    ///
+    /// Available to Rust only.
+    pub fn first_encodable(
+        candidates: &[&'static Encoding],
+        string: &str,
+    ) -> Option<&'static Encoding> {
+        candidates
+            .iter()
+            .find(|candidate| candidate.can_encode(string))
+            .map(|candidate| candidate.output_encoding())
+    }
+
+    /// Encodes `string` with the first encoding in `candidates` that can
+    /// encode it without replacing any unmappable characters.
+    ///
+    /// Returns the bytes and the encoding that was used or `None` if none of
+    /// the candidates can encode `string`. The returned encoding is the
+    /// output encoding of the candidate as with `encode()`.
+    ///
+    /// # Panics
+    ///
+    /// If the size calculation for a heap-allocated backing buffer overflows
+    /// `usize`.
+    ///
+    /// Available to Rust only.
+    pub fn encode_with_first_encodable<'a>(
+        candidates: &[&'static Encoding],
+        string: &'a str,
+    ) -> Option<(Cow<'a, [u8]>, &'static Encoding)> {
+        Encoding::first_encodable(candidates, string).map(|encoding| {
+            let (bytes, output_encoding, had_unmappables) = encoding.encode(string);
+            debug_assert!(!had_unmappables);
+            (bytes, output_encoding)
+        })
+    }
+
     fn new_variant_decoder(&'static self) -> VariantDecoder {
         self.variant.new_variant_decoder()
     }
@@ -3313,83 +4984,498 @@ impl Encoding {
         ascii_valid_up_to(bytes)
     }
 
-    /// Validates ISO-2022-JP ASCII-state data.
-    ///
-    /// Returns the index of the first byte that makes the input not
-    /// representable in the ASCII state of ISO-2022-JP or the length of the
-    /// slice if the slice is entirely representable in the ASCII state of
-    /// ISO-2022-JP.
+    /// Validates ISO-2022-JP ASCII-state data.
+    ///
+    /// Returns the index of the first byte that makes the input not
+    /// representable in the ASCII state of ISO-2022-JP or the length of the
+    /// slice if the slice is entirely representable in the ASCII state of
+    /// ISO-2022-JP.
+    ///
+    /// Available via the C wrapper.
+    pub fn iso_2022_jp_ascii_valid_up_to(bytes: &[u8]) -> usize {
+        iso_2022_jp_ascii_valid_up_to(bytes)
+    }
+}
+
+impl PartialEq for Encoding {
+    #[inline]
+    fn eq(&self, other: &Encoding) -> bool {
+        (self as *const Encoding) == (other as *const Encoding)
+    }
+}
+
+impl Eq for Encoding {}
+
+impl Hash for Encoding {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self as *const Encoding).hash(state);
+    }
+}
+
+impl std::fmt::Debug for Encoding {
+    #[inline]
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Encoding {{ {} }}", self.name)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for Encoding {
+    #[inline]
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.name)
+    }
+}
+
+#[cfg(feature = "serde")]
+struct EncodingVisitor;
+
+#[cfg(feature = "serde")]
+impl<'de> Visitor<'de> for EncodingVisitor {
+    type Value = &'static Encoding;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("a valid encoding label")
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<&'static Encoding, E>
+    where
+        E: serde::de::Error,
+    {
+        if let Some(enc) = Encoding::for_label(value.as_bytes()) {
+            Ok(enc)
+        } else {
+            Err(E::custom(format!("invalid encoding label: {}", value)))
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for &'static Encoding {
+    fn deserialize<D>(deserializer: D) -> Result<&'static Encoding, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_str(EncodingVisitor)
+    }
+}
+
+/// An iterator over the labels of an encoding.
+///
+/// Returned by [`Encoding::labels()`][1].
+///
+/// [1]: struct.Encoding.html#method.labels
+#[derive(Debug, Clone)]
+pub struct Labels {
+    encoding: &'static Encoding,
+    index: usize,
+}
+
+impl Iterator for Labels {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<&'static str> {
+        while self.index < LABELS_SORTED.len() {
+            let i = self.index;
+            self.index += 1;
+            if ENCODINGS_IN_LABEL_SORT[i] == self.encoding {
+                return Some(LABELS_SORTED[i]);
+            }
+        }
+        None
+    }
+}
+
+impl EncodingId {
+    /// Returns the encoding that this identifier identifies.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn encoding(self) -> &'static Encoding {
+        ENCODINGS[self as usize]
+    }
+
+    /// Returns the identifier whose discriminant is `value` or `None` if
+    /// there isn't one.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn from_u8(value: u8) -> Option<EncodingId> {
+        ENCODING_IDS.get(value as usize).cloned()
+    }
+}
+
+impl From<EncodingId> for &'static Encoding {
+    fn from(id: EncodingId) -> &'static Encoding {
+        id.encoding()
+    }
+}
+
+impl From<&'static Encoding> for EncodingId {
+    fn from(encoding: &'static Encoding) -> EncodingId {
+        encoding.id()
+    }
+}
+
+/// A set of encodings stored as a bit set indexed by `EncodingId`.
+///
+/// Available to Rust only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct EncodingSet {
+    bits: u64,
+}
+
+impl EncodingSet {
+    /// Creates an empty set.
+    #[inline]
+    pub fn new() -> EncodingSet {
+        EncodingSet { bits: 0 }
+    }
+
+    /// Creates a set that contains every encoding.
+    #[inline]
+    pub fn all() -> EncodingSet {
+        EncodingSet {
+            bits: (1u64 << ENCODINGS.len()) - 1,
+        }
+    }
+
+    /// Returns the bits of this set. Bit `n` is set if the set contains the
+    /// encoding whose `EncodingId` has the discriminant `n`.
+    #[inline]
+    pub fn bits(&self) -> u64 {
+        self.bits
+    }
+
+    /// Creates a set from the value returned by `bits()` or returns `None`
+    /// if a bit that doesn't correspond to an encoding is set.
+    #[inline]
+    pub fn from_bits(bits: u64) -> Option<EncodingSet> {
+        if bits & !EncodingSet::all().bits != 0 {
+            return None;
+        }
+        Some(EncodingSet { bits })
+    }
+
+    /// Adds an encoding to the set. Returns `false` if the set already
+    /// contained the encoding.
+    #[inline]
+    pub fn insert(&mut self, encoding: &'static Encoding) -> bool {
+        let bit = EncodingSet::bit(encoding);
+        let absent = self.bits & bit == 0;
+        self.bits |= bit;
+        absent
+    }
+
+    /// Removes an encoding from the set. Returns `false` if the set didn't
+    /// contain the encoding.
+    #[inline]
+    pub fn remove(&mut self, encoding: &'static Encoding) -> bool {
+        let bit = EncodingSet::bit(encoding);
+        let present = self.bits & bit != 0;
+        self.bits &= !bit;
+        present
+    }
+
+    /// Checks whether the set contains an encoding.
+    #[inline]
+    pub fn contains(&self, encoding: &'static Encoding) -> bool {
+        self.bits & EncodingSet::bit(encoding) != 0
+    }
+
+    /// Returns the number of encodings in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    /// Checks whether the set is empty.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    /// Returns the encodings that are in either set.
+    #[inline]
+    pub fn union(&self, other: &EncodingSet) -> EncodingSet {
+        EncodingSet {
+            bits: self.bits | other.bits,
+        }
+    }
+
+    /// Returns the encodings that are in both sets.
+    #[inline]
+    pub fn intersection(&self, other: &EncodingSet) -> EncodingSet {
+        EncodingSet {
+            bits: self.bits & other.bits,
+        }
+    }
+
+    /// Returns the encodings that are in this set but not in `other`.
+    #[inline]
+    pub fn difference(&self, other: &EncodingSet) -> EncodingSet {
+        EncodingSet {
+            bits: self.bits & !other.bits,
+        }
+    }
+
+    /// Returns the encodings that are in exactly one of the sets.
+    #[inline]
+    pub fn symmetric_difference(&self, other: &EncodingSet) -> EncodingSet {
+        EncodingSet {
+            bits: self.bits ^ other.bits,
+        }
+    }
+
+    /// Checks whether every encoding in this set is in `other`.
+    #[inline]
+    pub fn is_subset(&self, other: &EncodingSet) -> bool {
+        self.bits & !other.bits == 0
+    }
+
+    /// Checks whether every encoding in `other` is in this set.
+    #[inline]
+    pub fn is_superset(&self, other: &EncodingSet) -> bool {
+        other.is_subset(self)
+    }
+
+    /// Checks whether the sets have no encodings in common.
+    #[inline]
+    pub fn is_disjoint(&self, other: &EncodingSet) -> bool {
+        self.bits & other.bits == 0
+    }
+
+    /// Returns an iterator over the encodings in the set in the order of
+    /// `Encoding::all()`.
+    #[inline]
+    pub fn iter(&self) -> EncodingSetIter {
+        EncodingSetIter { bits: self.bits }
+    }
+
+    #[inline]
+    fn bit(encoding: &'static Encoding) -> u64 {
+        1u64 << (encoding.id() as u8)
+    }
+}
+
+impl std::ops::BitOr for EncodingSet {
+    type Output = EncodingSet;
+
+    fn bitor(self, other: EncodingSet) -> EncodingSet {
+        self.union(&other)
+    }
+}
+
+impl std::ops::BitAnd for EncodingSet {
+    type Output = EncodingSet;
+
+    fn bitand(self, other: EncodingSet) -> EncodingSet {
+        self.intersection(&other)
+    }
+}
+
+impl std::ops::BitXor for EncodingSet {
+    type Output = EncodingSet;
+
+    fn bitxor(self, other: EncodingSet) -> EncodingSet {
+        self.symmetric_difference(&other)
+    }
+}
+
+impl std::ops::Sub for EncodingSet {
+    type Output = EncodingSet;
+
+    fn sub(self, other: EncodingSet) -> EncodingSet {
+        self.difference(&other)
+    }
+}
+
+impl std::iter::FromIterator<&'static Encoding> for EncodingSet {
+    fn from_iter<I: IntoIterator<Item = &'static Encoding>>(iter: I) -> EncodingSet {
+        let mut set = EncodingSet::new();
+        for encoding in iter {
+            set.insert(encoding);
+        }
+        set
+    }
+}
+
+impl IntoIterator for EncodingSet {
+    type Item = &'static Encoding;
+    type IntoIter = EncodingSetIter;
+
+    fn into_iter(self) -> EncodingSetIter {
+        self.iter()
+    }
+}
+
+/// An iterator over the encodings in an `EncodingSet`.
+///
+/// Returned by [`EncodingSet::iter()`][1].
+///
+/// [1]: struct.EncodingSet.html#method.iter
+#[derive(Debug, Clone)]
+pub struct EncodingSetIter {
+    bits: u64,
+}
+
+impl Iterator for EncodingSetIter {
+    type Item = &'static Encoding;
+
+    fn next(&mut self) -> Option<&'static Encoding> {
+        if self.bits == 0 {
+            return None;
+        }
+        let index = self.bits.trailing_zeros() as usize;
+        self.bits &= self.bits - 1;
+        Some(ENCODINGS[index])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bits.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+/// A set of application-defined label aliases layered over the labels of the
+/// Encoding Standard.
+///
+/// Lookups try `Encoding::for_label()` first, so the labels of the Encoding
+/// Standard stay authoritative and can't be redefined by aliases. Aliases
+/// are compared after removing leading and trailing whitespace and
+/// ASCII-lowercasing like labels.
+///
+/// Available to Rust only.
+#[derive(Debug, Clone, Default)]
+pub struct LabelRegistry {
+    /// Normalized aliases sorted for binary search.
+    aliases: Vec<(Vec<u8>, &'static Encoding)>,
+}
+
+impl LabelRegistry {
+    /// Creates a registry without aliases.
+    pub fn new() -> LabelRegistry {
+        LabelRegistry {
+            aliases: Vec::new(),
+        }
+    }
+
+    /// Registers `alias` as a label of `encoding`, replacing an earlier
+    /// registration of the same alias.
     ///
-    /// Available via the C wrapper.
-    pub fn iso_2022_jp_ascii_valid_up_to(bytes: &[u8]) -> usize {
-        iso_2022_jp_ascii_valid_up_to(bytes)
+    /// Returns `false` without registering anything if `alias` is empty
+    /// after removing leading and trailing whitespace or if
+    /// `Encoding::for_label()` already accepts it.
+    pub fn register(&mut self, alias: &[u8], encoding: &'static Encoding) -> bool {
+        let trimmed = trim_label_whitespace(alias);
+        if trimmed.is_empty() || Encoding::for_label(trimmed).is_some() {
+            return false;
+        }
+        let normalized = trimmed.to_ascii_lowercase();
+        match self.aliases.binary_search_by(|probe| probe.0.cmp(&normalized)) {
+            Ok(i) => self.aliases[i].1 = encoding,
+            Err(i) => self.aliases.insert(i, (normalized, encoding)),
+        }
+        true
     }
-}
 
-impl PartialEq for Encoding {
-    #[inline]
-    fn eq(&self, other: &Encoding) -> bool {
-        (self as *const Encoding) == (other as *const Encoding)
+    /// Removes a registered alias. Returns the encoding the alias was
+    /// registered for or `None` if it wasn't registered.
+    pub fn unregister(&mut self, alias: &[u8]) -> Option<&'static Encoding> {
+        let normalized = trim_label_whitespace(alias).to_ascii_lowercase();
+        match self.aliases.binary_search_by(|probe| probe.0.cmp(&normalized)) {
+            Ok(i) => Some(self.aliases.remove(i).1),
+            Err(_) => None,
+        }
     }
-}
 
-impl Eq for Encoding {}
-
-impl Hash for Encoding {
-    #[inline]
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        (self as *const Encoding).hash(state);
+    /// Like `Encoding::for_label()` but also accepts the registered aliases.
+    pub fn for_label(&self, label: &[u8]) -> Option<&'static Encoding> {
+        if let Some(encoding) = Encoding::for_label(label) {
+            return Some(encoding);
+        }
+        let trimmed = trim_label_whitespace(label);
+        match self.aliases.binary_search_by(|probe| {
+            let mut candidate = trimmed.iter().map(|b| b.to_ascii_lowercase());
+            probe.0.iter().cloned().cmp(&mut candidate)
+        }) {
+            Ok(i) => Some(self.aliases[i].1),
+            Err(_) => None,
+        }
     }
-}
 
-impl std::fmt::Debug for Encoding {
-    #[inline]
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Encoding {{ {} }}", self.name)
+    /// Like `Encoding::for_label_no_replacement()` but also accepts the
+    /// registered aliases.
+    pub fn for_label_no_replacement(&self, label: &[u8]) -> Option<&'static Encoding> {
+        match self.for_label(label) {
+            Some(encoding) if encoding != REPLACEMENT => Some(encoding),
+            _ => None,
+        }
     }
 }
 
-#[cfg(feature = "serde")]
-impl Serialize for Encoding {
-    #[inline]
-    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
-    where
-        S: Serializer,
-    {
-        serializer.serialize_str(self.name)
-    }
+/// A suggested encoding for a label that `for_label()` rejected.
+///
+/// Returned by [`Encoding::suggest_labels()`][1].
+///
+/// [1]: struct.Encoding.html#method.suggest_labels
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LabelSuggestion {
+    label: &'static str,
+    encoding: &'static Encoding,
+    confidence: f32,
 }
 
-#[cfg(feature = "serde")]
-struct EncodingVisitor;
+impl LabelSuggestion {
+    /// Returns the label of the encoding that is closest to the input.
+    pub fn label(&self) -> &'static str {
+        self.label
+    }
 
-#[cfg(feature = "serde")]
-impl<'de> Visitor<'de> for EncodingVisitor {
-    type Value = &'static Encoding;
+    /// Returns the suggested encoding.
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
 
-    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
-        formatter.write_str("a valid encoding label")
+    /// Returns how closely the label matches the input from 0.5 to 1.0.
+    pub fn confidence(&self) -> f32 {
+        self.confidence
     }
+}
 
-    fn visit_str<E>(self, value: &str) -> Result<&'static Encoding, E>
-    where
-        E: serde::de::Error,
-    {
-        if let Some(enc) = Encoding::for_label(value.as_bytes()) {
-            Ok(enc)
-        } else {
-            Err(E::custom(format!("invalid encoding label: {}", value)))
+/// The result of looking up a platform charset name with
+/// [`Encoding::for_platform_name()`][1].
+///
+/// [1]: struct.Encoding.html#method.for_platform_name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LabelMatch {
+    /// The name denotes the encoding.
+    Exact(&'static Encoding),
+
+    /// The name denotes a charset that differs from the encoding in some
+    /// byte sequences or characters, e.g. a subset or an extension of it.
+    /// The encoding is the closest one in the Encoding Standard.
+    Approximate(&'static Encoding),
+}
+
+impl LabelMatch {
+    /// Returns the encoding.
+    pub fn encoding(&self) -> &'static Encoding {
+        match *self {
+            LabelMatch::Exact(encoding) | LabelMatch::Approximate(encoding) => encoding,
         }
     }
-}
 
-#[cfg(feature = "serde")]
-impl<'de> Deserialize<'de> for &'static Encoding {
-    fn deserialize<D>(deserializer: D) -> Result<&'static Encoding, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        deserializer.deserialize_str(EncodingVisitor)
+    /// Checks whether the match is exact.
+    pub fn is_exact(&self) -> bool {
+        match *self {
+            LabelMatch::Exact(_) => true,
+            LabelMatch::Approximate(_) => false,
+        }
     }
 }
 
@@ -3489,6 +5575,196 @@ pub enum DecoderResult {
     Malformed(u8, u8), // u8 instead of usize to avoid useless bloat
 }
 
+/// Error from a non-streaming decode with malformed sequences treated as
+/// fatal.
+///
+/// Returned by [`Encoding::try_decode_without_bom_handling()`][1]. Locates the
+/// first malformed byte sequence in the input. The malformed sequence is
+/// at most four bytes long.
+///
+/// [1]: struct.Encoding.html#method.try_decode_without_bom_handling
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    encoding: &'static Encoding,
+    offset: usize,
+    malformed: [u8; 4],
+    malformed_len: u8,
+}
+
+impl DecodeError {
+    fn new(encoding: &'static Encoding, bytes: &[u8], offset: usize, length: usize) -> DecodeError {
+        debug_assert!(length <= 4);
+        let mut malformed = [0u8; 4];
+        malformed[..length].copy_from_slice(&bytes[offset..offset + length]);
+        DecodeError {
+            encoding,
+            offset,
+            malformed,
+            malformed_len: length as u8,
+        }
+    }
+
+    /// The `Encoding` whose decoder found the malformed sequence.
+    #[inline]
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// The index of the first byte of the malformed sequence in the input.
+    #[inline]
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// The bytes of the malformed sequence.
+    #[inline]
+    pub fn malformed_sequence(&self) -> &[u8] {
+        &self.malformed[..self.malformed_len as usize]
+    }
+}
+
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "invalid {} at byte {}", self.encoding.name, self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {
+    fn description(&self) -> &str {
+        "malformed byte sequence"
+    }
+}
+
+/// The state that `Decoder` keeps across calls only for the decode modes
+/// that report or escape malformed sequences.
+struct MalformedState {
+    /// The number of bytes consumed from the start of the stream by calls
+    /// to `decode_to_string_with_malformed_spans()`.
+    bytes_read: usize,
+    /// The last bytes consumed from the stream by the decode modes that
+    /// pass on malformed bytes, most recent last.
+    recent_bytes: [u8; RECENT_BYTES_LENGTH],
+    /// Bytes of a malformed sequence whose surrogate escapes didn't fit in
+    /// the output buffer.
+    pending_escapes: [u8; RECENT_BYTES_LENGTH],
+    pending_escapes_start: u8,
+    pending_escapes_end: u8,
+}
+
+impl MalformedState {
+    fn new() -> MalformedState {
+        MalformedState {
+            bytes_read: 0,
+            recent_bytes: [0u8; RECENT_BYTES_LENGTH],
+            pending_escapes: [0u8; RECENT_BYTES_LENGTH],
+            pending_escapes_start: 0,
+            pending_escapes_end: 0,
+        }
+    }
+
+    /// Appends the bytes consumed by a `decode_*` call to `recent_bytes`.
+    fn remember_read(&mut self, read: &[u8]) {
+        if read.len() >= RECENT_BYTES_LENGTH {
+            self.recent_bytes
+                .copy_from_slice(&read[read.len() - RECENT_BYTES_LENGTH..]);
+        } else {
+            let kept = RECENT_BYTES_LENGTH - read.len();
+            for i in 0..kept {
+                self.recent_bytes[i] = self.recent_bytes[i + read.len()];
+            }
+            self.recent_bytes[kept..].copy_from_slice(read);
+        }
+    }
+
+    /// The bytes of the malformed sequence reported by a `Malformed` result
+    /// of the most recent `decode_*` call.
+    fn malformed_bytes(&self, malformed: u8, consumed_after: u8) -> [u8; RECENT_BYTES_LENGTH] {
+        let end = RECENT_BYTES_LENGTH - consumed_after as usize;
+        let start = end - malformed as usize;
+        let mut bytes = [0u8; RECENT_BYTES_LENGTH];
+        bytes[..malformed as usize].copy_from_slice(&self.recent_bytes[start..end]);
+        bytes
+    }
+}
+
+/// The location of a malformed byte sequence that was replaced with the
+/// REPLACEMENT CHARACTER.
+///
+/// Reported by [`Encoding::decode_with_malformed_spans()`][1] and
+/// [`Decoder::decode_to_string_with_malformed_spans()`][2]. The input range
+/// is in bytes counted from the start of the stream, including a BOM if
+/// there was one. The output range is in bytes of UTF-8 and covers the
+/// REPLACEMENT CHARACTER that was written in place of the malformed
+/// sequence.
+///
+/// [1]: struct.Encoding.html#method.decode_with_malformed_spans
+/// [2]: struct.Decoder.html#method.decode_to_string_with_malformed_spans
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedSpan {
+    input: Range<usize>,
+    output: Range<usize>,
+}
+
+impl MalformedSpan {
+    /// The range of the malformed bytes in the input.
+    #[inline]
+    pub fn input(&self) -> Range<usize> {
+        self.input.clone()
+    }
+
+    /// The range of the REPLACEMENT CHARACTER in the output.
+    #[inline]
+    pub fn output(&self) -> Range<usize> {
+        self.output.clone()
+    }
+}
+
+/// Built-in ways to handle malformed byte sequences for
+/// [`Encoding::decode_with_malformed_policy()`][1].
+///
+/// [1]: struct.Encoding.html#method.decode_with_malformed_policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MalformedPolicy<'a> {
+    /// Append one REPLACEMENT CHARACTER (U+FFFD) per malformed sequence as
+    /// per the Encoding Standard.
+    Replace,
+
+    /// Append nothing.
+    ///
+    /// Note that silently dropping malformed sequences may have security
+    /// implications when the output is subsequently parsed.
+    Skip,
+
+    /// Append each byte of the malformed sequence as `\xNN` where `NN` is
+    /// the value of the byte in upper-case hexadecimal.
+    Escape,
+
+    /// Append the wrapped string once per malformed sequence.
+    Substitute(&'a str),
+}
+
+impl<'a> MalformedPolicy<'a> {
+    /// Appends to `dst` what this policy calls for in place of the malformed
+    /// byte sequence `malformed`.
+    ///
+    /// This is the handler that `Encoding::decode_with_malformed_policy()`
+    /// passes to `Encoding::decode_with_malformed_handler()`.
+    pub fn handle(&self, malformed: &[u8], dst: &mut String) {
+        match *self {
+            MalformedPolicy::Replace => dst.push('\u{FFFD}'),
+            MalformedPolicy::Skip => {}
+            MalformedPolicy::Escape => {
+                for &b in malformed {
+                    dst.push_str("\\x");
+                    dst.push(HEX_UPPER[(b >> 4) as usize] as char);
+                    dst.push(HEX_UPPER[(b & 0xF) as usize] as char);
+                }
+            }
+            MalformedPolicy::Substitute(s) => dst.push_str(s),
+        }
+    }
+}
+
 /// A converter that decodes a byte stream into Unicode according to a
 /// character encoding in a streaming (incremental) manner.
 ///
@@ -3590,6 +5866,9 @@ pub struct Decoder {
     encoding: &'static Encoding,
     variant: VariantDecoder,
     life_cycle: DecoderLifeCycle,
+    /// Only used by the decode modes that report or escape malformed
+    /// sequences.
+    malformed_state: Option<Box<MalformedState>>,
 }
 
 impl Decoder {
@@ -3612,9 +5891,17 @@ impl Decoder {
                     }
                 }
             },
+            malformed_state: None,
         }
     }
 
+    /// The state of the decode modes that report or escape malformed
+    /// sequences, allocated upon first use so that other callers don't pay
+    /// for it.
+    fn malformed_state(&mut self) -> &mut MalformedState {
+        self.malformed_state.get_or_insert_with(|| Box::new(MalformedState::new()))
+    }
+
     /// The `Encoding` this `Decoder` is for.
     ///
     /// BOM sniffing can change the return value of this method during the life
@@ -3943,6 +6230,135 @@ impl Decoder {
         }
     }
 
+    /// Incrementally decode a byte stream into UTF-8 with malformed sequences
+    /// replaced with the REPLACEMENT CHARACTER using a `String` receiver and
+    /// recording the location of each replacement.
+    ///
+    /// This method behaves the same as `decode_to_string()`, except each
+    /// replacement is additionally appended to `spans` as a
+    /// [`MalformedSpan`][1]. The input range of the span is counted from the
+    /// start of the stream, i.e. across all calls to this method, so all the
+    /// input should be passed to this decoder via this method. The output
+    /// range of the span is an index into `dst`.
+    ///
+    /// [1]: struct.MalformedSpan.html
+    ///
+    /// See the documentation of the struct for documentation for `decode_*`
+    /// methods collectively.
+    ///
+    /// Available to Rust only.
+    pub fn decode_to_string_with_malformed_spans(
+        &mut self,
+        src: &[u8],
+        dst: &mut String,
+        last: bool,
+        spans: &mut Vec<MalformedSpan>,
+    ) -> (CoderResult, usize, bool) {
+        let mut had_errors = false;
+        let mut total_read = 0usize;
+        unsafe {
+            let vec = dst.as_mut_vec();
+            let mut total_written = vec.len();
+            let capacity = vec.capacity();
+            vec.set_len(capacity);
+            loop {
+                let (result, read, written) = self.decode_to_utf8_without_replacement(
+                    &src[total_read..],
+                    &mut vec[total_written..],
+                    last,
+                );
+                total_read += read;
+                total_written += written;
+                let bytes_read = {
+                    let state = self.malformed_state();
+                    state.bytes_read = state.bytes_read.wrapping_add(read);
+                    state.bytes_read
+                };
+                match result {
+                    DecoderResult::InputEmpty => {
+                        vec.set_len(total_written);
+                        return (CoderResult::InputEmpty, total_read, had_errors);
+                    }
+                    DecoderResult::OutputFull => {
+                        vec.set_len(total_written);
+                        return (CoderResult::OutputFull, total_read, had_errors);
+                    }
+                    DecoderResult::Malformed(malformed, consumed_after) => {
+                        had_errors = true;
+                        // The malformed bytes may have been part of an
+                        // earlier input buffer, so count from the start of
+                        // the stream.
+                        let end = bytes_read.wrapping_sub(consumed_after as usize);
+                        spans.push(MalformedSpan {
+                            input: end.wrapping_sub(malformed as usize)..end,
+                            output: total_written..total_written + 3,
+                        });
+                        // There should always be space for the U+FFFD, because
+                        // otherwise we'd have gotten OutputFull already.
+                        vec[total_written] = 0xEFu8;
+                        total_written += 1;
+                        vec[total_written] = 0xBFu8;
+                        total_written += 1;
+                        vec[total_written] = 0xBDu8;
+                        total_written += 1;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Incrementally decode a byte stream into UTF-8 with malformed sequences
+    /// handled by a caller-supplied function using a `String` receiver.
+    ///
+    /// This method behaves the same as `decode_to_string()`, except instead
+    /// of appending the REPLACEMENT CHARACTER for each malformed sequence,
+    /// `handler` is called with the bytes of the malformed sequence and
+    /// `dst`. The handler may append anything to `dst` (or nothing). The
+    /// bytes of the malformed sequence are passed even if some of them were
+    /// part of an earlier input buffer that was passed to this method. The
+    /// boolean in the returned tuple indicates whether `handler` was called
+    /// during this call.
+    ///
+    /// Unlike `decode_to_string()`, this method may grow `dst` beyond its
+    /// capacity if the handler appends to it.
+    ///
+    /// See the documentation of the struct for documentation for `decode_*`
+    /// methods collectively.
+    ///
+    /// Available to Rust only.
+    pub fn decode_to_string_with_malformed_handler<F>(
+        &mut self,
+        src: &[u8],
+        dst: &mut String,
+        last: bool,
+        mut handler: F,
+    ) -> (CoderResult, usize, bool)
+    where
+        F: FnMut(&[u8], &mut String),
+    {
+        let mut had_errors = false;
+        let mut total_read = 0usize;
+        loop {
+            let (result, read) =
+                self.decode_to_string_without_replacement(&src[total_read..], dst, last);
+            self.malformed_state().remember_read(&src[total_read..total_read + read]);
+            total_read += read;
+            match result {
+                DecoderResult::InputEmpty => {
+                    return (CoderResult::InputEmpty, total_read, had_errors);
+                }
+                DecoderResult::OutputFull => {
+                    return (CoderResult::OutputFull, total_read, had_errors);
+                }
+                DecoderResult::Malformed(malformed, consumed_after) => {
+                    had_errors = true;
+                    let bytes = self.malformed_state().malformed_bytes(malformed, consumed_after);
+                    handler(&bytes[..malformed as usize], dst);
+                }
+            }
+        }
+    }
+
     public_decode_function!(/// Incrementally decode a byte stream into UTF-8
                             /// _without replacement_.
                             ///
@@ -4114,19 +6530,90 @@ impl Decoder {
                     }
                 }
             }
-            DecoderLifeCycle::Finished => panic!("Must not use a decoder that has finished."),
+            DecoderLifeCycle::Finished => panic!("Must not use a decoder that has finished."),
+        }
+        None
+    }
+
+    /// Incrementally decode a byte stream into UTF-16 with malformed sequences
+    /// replaced with the REPLACEMENT CHARACTER.
+    ///
+    /// See the documentation of the struct for documentation for `decode_*`
+    /// methods collectively.
+    ///
+    /// Available via the C wrapper.
+    pub fn decode_to_utf16(
+        &mut self,
+        src: &[u8],
+        dst: &mut [u16],
+        last: bool,
+    ) -> (CoderResult, usize, usize, bool) {
+        let mut had_errors = false;
+        let mut total_read = 0usize;
+        let mut total_written = 0usize;
+        loop {
+            let (result, read, written) = self.decode_to_utf16_without_replacement(
+                &src[total_read..],
+                &mut dst[total_written..],
+                last,
+            );
+            total_read += read;
+            total_written += written;
+            match result {
+                DecoderResult::InputEmpty => {
+                    return (
+                        CoderResult::InputEmpty,
+                        total_read,
+                        total_written,
+                        had_errors,
+                    );
+                }
+                DecoderResult::OutputFull => {
+                    return (
+                        CoderResult::OutputFull,
+                        total_read,
+                        total_written,
+                        had_errors,
+                    );
+                }
+                DecoderResult::Malformed(_, _) => {
+                    had_errors = true;
+                    // There should always be space for the U+FFFD, because
+                    // otherwise we'd have gotten OutputFull already.
+                    dst[total_written] = 0xFFFD;
+                    total_written += 1;
+                }
+            }
         }
-        None
     }
 
-    /// Incrementally decode a byte stream into UTF-16 with malformed sequences
-    /// replaced with the REPLACEMENT CHARACTER.
+    /// Incrementally decode a byte stream into UTF-16 with each byte of
+    /// malformed sequences escaped as an unpaired low surrogate.
+    ///
+    /// This is the streaming counterpart of
+    /// [`Encoding::decode_to_utf16_with_surrogate_escapes()`][1]: Each byte
+    /// of a malformed sequence becomes the unpaired surrogate whose value is
+    /// 0xDC00 plus the byte value, including bytes that were part of an
+    /// earlier input buffer that was passed to this method. The boolean in
+    /// the returned tuple indicates whether there were malformed sequences
+    /// during this call.
+    ///
+    /// Since a malformed sequence can take more output space than a
+    /// REPLACEMENT CHARACTER would, this method may return `OutputFull` even
+    /// if the output buffer is as long as `max_utf16_buffer_length()`
+    /// returned. In that case, the escapes that didn't fit are written upon
+    /// the next call.
+    ///
+    /// The first call allocates the small amount of state that the decoder
+    /// keeps for this mode across calls.
     ///
     /// See the documentation of the struct for documentation for `decode_*`
     /// methods collectively.
     ///
-    /// Available via the C wrapper.
-    pub fn decode_to_utf16(
+    /// Available to Rust only.
+    ///
+    /// [1]: struct.Encoding.html#method.decode_to_utf16_with_surrogate_escapes
+    pub fn decode_to_utf16_with_surrogate_escapes(
         &mut self,
         src: &[u8],
         dst: &mut [u16],
@@ -4136,36 +6623,39 @@ impl Decoder {
         let mut total_read = 0usize;
         let mut total_written = 0usize;
         loop {
+            {
+                let state = self.malformed_state();
+                while state.pending_escapes_start < state.pending_escapes_end {
+                    if total_written == dst.len() {
+                        return (CoderResult::OutputFull, total_read, total_written, had_errors);
+                    }
+                    let b = state.pending_escapes[state.pending_escapes_start as usize];
+                    dst[total_written] = 0xDC00u16 | u16::from(b);
+                    total_written += 1;
+                    state.pending_escapes_start += 1;
+                }
+            }
             let (result, read, written) = self.decode_to_utf16_without_replacement(
                 &src[total_read..],
                 &mut dst[total_written..],
                 last,
             );
+            self.malformed_state().remember_read(&src[total_read..total_read + read]);
             total_read += read;
             total_written += written;
             match result {
                 DecoderResult::InputEmpty => {
-                    return (
-                        CoderResult::InputEmpty,
-                        total_read,
-                        total_written,
-                        had_errors,
-                    );
+                    return (CoderResult::InputEmpty, total_read, total_written, had_errors);
                 }
                 DecoderResult::OutputFull => {
-                    return (
-                        CoderResult::OutputFull,
-                        total_read,
-                        total_written,
-                        had_errors,
-                    );
+                    return (CoderResult::OutputFull, total_read, total_written, had_errors);
                 }
-                DecoderResult::Malformed(_, _) => {
+                DecoderResult::Malformed(malformed, consumed_after) => {
                     had_errors = true;
-                    // There should always be space for the U+FFFD, because
-                    // otherwise we'd have gotten OutputFull already.
-                    dst[total_written] = 0xFFFD;
-                    total_written += 1;
+                    let state = self.malformed_state();
+                    state.pending_escapes = state.malformed_bytes(malformed, consumed_after);
+                    state.pending_escapes_start = 0;
+                    state.pending_escapes_end = malformed;
                 }
             }
         }
@@ -4221,6 +6711,142 @@ impl EncoderResult {
     }
 }
 
+/// Built-in ways to replace unmappable characters for
+/// [`Encoding::encode_with_unmappable_policy()`][1].
+///
+/// [1]: struct.Encoding.html#method.encode_with_unmappable_policy
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnmappablePolicy<'a> {
+    /// Replace with an HTML decimal numeric character reference as per the
+    /// Encoding Standard.
+    Ncr,
+
+    /// Replace with the wrapped string encoded in the target encoding.
+    /// Characters of the string that are themselves unmappable are replaced
+    /// with HTML decimal numeric character references.
+    ///
+    /// For example, `Substitute("?")` replaces each unmappable character with
+    /// the byte 0x3F in all ASCII-compatible encodings.
+    Substitute(&'a str),
+
+    /// Replace with an HTML hexadecimal numeric character reference, e.g.
+    /// `&#x3042;`.
+    HexNcr,
+
+    /// Replace with a JavaScript / JSON string escape, e.g. `\u3042`. Astral
+    /// characters are escaped as a surrogate pair, e.g. `\uD83D\uDCA9`.
+    JavaScript,
+
+    /// Replace with a CSS escape followed by a space, e.g. `\3042 `.
+    Css,
+
+    /// Replace with a percent-encoded HTML decimal numeric character
+    /// reference, e.g. `%26%2312354%3B`, as generated by the URL Standard
+    /// for query strings in legacy encodings.
+    PercentEncodedNcr,
+
+    /// Replace with an ASCII approximation in the manner of the best-fit
+    /// mappings of Windows, e.g. `e` for `\u{11B}`, `"` for `\u{201C}` or
+    /// `A` for `\u{FF21}`, and with an HTML decimal numeric character
+    /// reference if there is no approximation.
+    BestFit,
+}
+
+impl<'a> UnmappablePolicy<'a> {
+    /// Appends to `dst` the text that this policy calls for in place of the
+    /// unmappable character `unmappable`.
+    ///
+    /// This is the handler that `Encoding::encode_with_unmappable_policy()`
+    /// passes to `Encoding::encode_with_unmappable_handler()`.
+    pub fn handle(&self, unmappable: char, dst: &mut String) {
+        use std::fmt::Write;
+        let code_point = unmappable as u32;
+        // Writing to a `String` doesn't fail.
+        match *self {
+            UnmappablePolicy::Ncr => {
+                let mut ncr = [0u8; NCR_EXTRA];
+                let len = write_ncr(unmappable, &mut ncr[..]);
+                dst.push_str(unsafe { std::str::from_utf8_unchecked(&ncr[..len]) });
+            }
+            UnmappablePolicy::Substitute(s) => dst.push_str(s),
+            UnmappablePolicy::HexNcr => {
+                let _ = write!(dst, "&#x{:X};", code_point);
+            }
+            UnmappablePolicy::JavaScript => {
+                let mut utf16 = [0u16; 2];
+                for unit in unmappable.encode_utf16(&mut utf16).iter() {
+                    let _ = write!(dst, "\\u{:04X}", unit);
+                }
+            }
+            UnmappablePolicy::Css => {
+                let _ = write!(dst, "\\{:X} ", code_point);
+            }
+            UnmappablePolicy::PercentEncodedNcr => {
+                let _ = write!(dst, "%26%23{}%3B", code_point);
+            }
+            UnmappablePolicy::BestFit => {
+                if code_point <= 0xFFFF {
+                    if let Some(approximation) = data::best_fit(code_point as u16) {
+                        dst.push_str(approximation);
+                        return;
+                    }
+                }
+                UnmappablePolicy::Ncr.handle(unmappable, dst);
+            }
+        }
+    }
+
+    /// Returns the length in bytes of the longest text that `handle()` can
+    /// append and whether the text is always ASCII.
+    fn longest_replacement(&self) -> (usize, bool) {
+        match *self {
+            UnmappablePolicy::Ncr => (NCR_EXTRA, true),
+            UnmappablePolicy::Substitute(s) => (s.len(), is_always_mappable(s)),
+            UnmappablePolicy::HexNcr => (10, true), // &#x10FFFF;
+            UnmappablePolicy::JavaScript => (12, true), // \uDBFF\uDFFF
+            UnmappablePolicy::Css => (8, true), // \10FFFF followed by a space
+            UnmappablePolicy::PercentEncodedNcr => (16, true), // %26%231114111%3B
+            UnmappablePolicy::BestFit => (
+                std::cmp::max(NCR_EXTRA, data::BEST_FIT_LONGEST_REPLACEMENT),
+                true,
+            ),
+        }
+    }
+}
+
+/// An iterator over the unmappable characters of a string and their indices.
+///
+/// Returned by [`Encoding::unmappables()`][1].
+///
+/// [1]: struct.Encoding.html#method.unmappables
+pub struct Unmappables<'a> {
+    variant: Option<VariantEncoder>,
+    string: &'a str,
+    position: usize,
+}
+
+impl<'a> Iterator for Unmappables<'a> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let variant = match self.variant {
+            Some(ref variant) => variant,
+            None => return None,
+        };
+        let string = self.string;
+        let start = self.position;
+        for (offset, c) in string[start..].char_indices() {
+            if !variant.is_mappable(c) {
+                let index = start + offset;
+                self.position = index + c.len_utf8();
+                return Some((index, c));
+            }
+        }
+        self.position = string.len();
+        None
+    }
+}
+
 /// A converter that encodes a Unicode stream into bytes according to a
 /// character encoding in a streaming (incremental) manner.
 ///
@@ -4334,6 +6960,11 @@ impl EncoderResult {
 pub struct Encoder {
     encoding: &'static Encoding,
     variant: VariantEncoder,
+    /// The replacement most recently supplied by an unmappable handler.
+    replacement: String,
+    /// The unmappable character `replacement` is for if it didn't fit in
+    /// the output buffer and the character was unread.
+    replacement_for: Option<char>,
 }
 
 impl Encoder {
@@ -4341,6 +6972,8 @@ impl Encoder {
         Encoder {
             encoding: enc,
             variant: encoder,
+            replacement: String::new(),
+            replacement_for: None,
         }
     }
 
@@ -4681,6 +7314,406 @@ impl Encoder {
     ) -> (EncoderResult, usize, usize) {
         self.variant.encode_from_utf16_raw(src, dst, last)
     }
+
+    /// Incrementally encode into byte stream from UTF-8 with unmappable
+    /// characters replaced with text supplied by `handler`.
+    ///
+    /// For each unmappable character, `handler` is called with the character
+    /// and an empty `String` to append the replacement text to. The
+    /// replacement text is then encoded into the output. Characters in the
+    /// replacement text that are themselves unmappable are replaced with
+    /// HTML (decimal) numeric character references. (For example, `"?"` is a
+    /// replacement that encodes to a single byte in every ASCII-compatible
+    /// encoding.)
+    ///
+    /// If there isn't enough space in `dst` for the encoded replacement
+    /// text, the unmappable character is left unread and `OutputFull` is
+    /// returned. The encoder keeps the replacement text, so when the same
+    /// character is reported again upon the next call, the handler isn't
+    /// called again. That is, the handler is called exactly once per
+    /// unmappable character. Therefore, no space needs to be reserved for the
+    /// replacement in advance, and `max_buffer_length_from_utf8_without_replacement()`
+    /// gives the length of the output buffer that won't overflow in the
+    /// absence of unmappable characters. However, in order to make progress,
+    /// `dst` needs to be long enough for the replacement text with every
+    /// character of it expanded into a numeric character reference plus
+    /// the length of one more numeric character reference.
+    ///
+    /// See the documentation of the struct for documentation for `encode_*`
+    /// methods collectively.
+    ///
+    /// Available to Rust only.
+    pub fn encode_from_utf8_with_unmappable_handler<F>(
+        &mut self,
+        src: &str,
+        dst: &mut [u8],
+        last: bool,
+        mut handler: F,
+    ) -> (CoderResult, usize, usize, bool)
+    where
+        F: FnMut(char, &mut String),
+    {
+        let mut had_unmappables = false;
+        let mut total_read = 0usize;
+        let mut total_written = 0usize;
+        loop {
+            let (result, read, written) = self.encode_from_utf8_without_replacement(
+                &src[total_read..],
+                &mut dst[total_written..],
+                last,
+            );
+            total_read += read;
+            total_written += written;
+            match result {
+                EncoderResult::InputEmpty => {
+                    return (
+                        CoderResult::InputEmpty,
+                        total_read,
+                        total_written,
+                        had_unmappables,
+                    );
+                }
+                EncoderResult::OutputFull => {
+                    return (
+                        CoderResult::OutputFull,
+                        total_read,
+                        total_written,
+                        had_unmappables,
+                    );
+                }
+                EncoderResult::Unmappable(unmappable) => {
+                    let replacement = self.take_replacement(unmappable, &mut handler);
+                    let written = self.write_replacement(&replacement, &mut dst[total_written..]);
+                    self.replacement = replacement;
+                    if let Some(written) = written {
+                        self.replacement_for = None;
+                        had_unmappables = true;
+                        total_written += written;
+                        continue;
+                    }
+                    // Unread the unmappable so that it gets reported again
+                    // when there's more space.
+                    total_read -= unmappable.len_utf8();
+                    return (
+                        CoderResult::OutputFull,
+                        total_read,
+                        total_written,
+                        had_unmappables,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Incrementally encode into byte stream from UTF-16 with unmappable
+    /// characters replaced with text supplied by `handler`.
+    ///
+    /// This method behaves the same as
+    /// `encode_from_utf8_with_unmappable_handler()` except for the input
+    /// being UTF-16. `max_buffer_length_from_utf16_without_replacement()`
+    /// gives the length of the output buffer that won't overflow in the
+    /// absence of unmappable characters.
+    ///
+    /// See the documentation of the struct for documentation for `encode_*`
+    /// methods collectively.
+    ///
+    /// Available to Rust only.
+    pub fn encode_from_utf16_with_unmappable_handler<F>(
+        &mut self,
+        src: &[u16],
+        dst: &mut [u8],
+        last: bool,
+        mut handler: F,
+    ) -> (CoderResult, usize, usize, bool)
+    where
+        F: FnMut(char, &mut String),
+    {
+        let mut had_unmappables = false;
+        let mut total_read = 0usize;
+        let mut total_written = 0usize;
+        loop {
+            let (result, read, written) = self.encode_from_utf16_without_replacement(
+                &src[total_read..],
+                &mut dst[total_written..],
+                last,
+            );
+            total_read += read;
+            total_written += written;
+            match result {
+                EncoderResult::InputEmpty => {
+                    return (
+                        CoderResult::InputEmpty,
+                        total_read,
+                        total_written,
+                        had_unmappables,
+                    );
+                }
+                EncoderResult::OutputFull => {
+                    return (
+                        CoderResult::OutputFull,
+                        total_read,
+                        total_written,
+                        had_unmappables,
+                    );
+                }
+                EncoderResult::Unmappable(unmappable) => {
+                    let replacement = self.take_replacement(unmappable, &mut handler);
+                    let written = self.write_replacement(&replacement, &mut dst[total_written..]);
+                    self.replacement = replacement;
+                    if let Some(written) = written {
+                        self.replacement_for = None;
+                        had_unmappables = true;
+                        total_written += written;
+                        continue;
+                    }
+                    // Unread the unmappable so that it gets reported again
+                    // when there's more space. (An unpaired surrogate is
+                    // reported as U+FFFD, which has the same length.)
+                    total_read -= unmappable.len_utf16();
+                    return (
+                        CoderResult::OutputFull,
+                        total_read,
+                        total_written,
+                        had_unmappables,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Returns the replacement for `unmappable`, calling `handler` unless the
+    /// replacement is still kept from a call that ran out of output space.
+    fn take_replacement<F>(&mut self, unmappable: char, handler: &mut F) -> String
+    where
+        F: FnMut(char, &mut String),
+    {
+        let mut replacement = String::new();
+        std::mem::swap(&mut replacement, &mut self.replacement);
+        if self.replacement_for != Some(unmappable) {
+            replacement.clear();
+            handler(unmappable, &mut replacement);
+            self.replacement_for = Some(unmappable);
+        }
+        replacement
+    }
+
+    /// Encodes the replacement for an unmappable character. Returns `None`
+    /// without writing anything if `dst` might be too short.
+    fn write_replacement(&mut self, replacement: &str, dst: &mut [u8]) -> Option<usize> {
+        if replacement.is_empty() {
+            return Some(0);
+        }
+        let ascii = is_always_mappable(replacement);
+        match self.replacement_buffer_length(replacement.len(), ascii) {
+            Some(needed) if needed <= dst.len() => {
+                let dst = &mut dst[..needed];
+                if ascii {
+                    let (result, read, written) =
+                        self.encode_from_utf8_without_replacement(replacement, dst, false);
+                    debug_assert_eq!(result, EncoderResult::InputEmpty);
+                    debug_assert_eq!(read, replacement.len());
+                    return Some(written);
+                }
+                let (result, read, written, _) = self.encode_from_utf8(replacement, dst, false);
+                debug_assert_eq!(result, CoderResult::InputEmpty);
+                debug_assert_eq!(read, replacement.len());
+                Some(written)
+            }
+            _ => None,
+        }
+    }
+
+    /// The output space that `write_replacement()` needs for a replacement
+    /// of `length` bytes.
+    fn replacement_buffer_length(&self, length: usize, ascii: bool) -> Option<usize> {
+        let without_replacement = self.max_buffer_length_from_utf8_without_replacement(length);
+        if ascii {
+            return without_replacement;
+        }
+        // Leave room for a numeric character reference for each character
+        // of the replacement plus the slack `encode_from_utf8()` needs.
+        checked_add_opt(
+            checked_mul(NCR_EXTRA, length.checked_add(1)),
+            without_replacement,
+        )
+    }
+
+    /// Query the worst-case output size when encoding from UTF-8 with
+    /// unmappable characters replaced according to `policy`.
+    ///
+    /// Returns the size of the output buffer in bytes that will not overflow
+    /// given the current state of the encoder and `byte_length` number of
+    /// additional input code units if there are no unmappable characters in
+    /// the input and that has room for one replacement if there are or
+    /// `None` if `usize` would overflow. An output buffer of this size
+    /// guarantees progress when calling
+    /// `encode_from_utf8_with_unmappable_handler()` with `policy.handle()`
+    /// as the handler.
+    ///
+    /// Available to Rust only.
+    pub fn max_buffer_length_from_utf8_with_unmappable_policy(
+        &self,
+        byte_length: usize,
+        policy: UnmappablePolicy,
+    ) -> Option<usize> {
+        let without_replacement = self.max_buffer_length_from_utf8_without_replacement(byte_length);
+        if self.encoding().can_encode_everything() {
+            return without_replacement;
+        }
+        let (length, ascii) = policy.longest_replacement();
+        checked_add_opt(
+            without_replacement,
+            self.replacement_buffer_length(length, ascii),
+        )
+    }
+
+    /// Query the worst-case output size when encoding from UTF-16 with
+    /// unmappable characters replaced according to `policy`.
+    ///
+    /// Returns the size of the output buffer in bytes that will not overflow
+    /// given the current state of the encoder and `u16_length` number of
+    /// additional input code units if there are no unmappable characters in
+    /// the input and that has room for one replacement if there are or
+    /// `None` if `usize` would overflow. An output buffer of this size
+    /// guarantees progress when calling
+    /// `encode_from_utf16_with_unmappable_handler()` with `policy.handle()`
+    /// as the handler.
+    ///
+    /// Available to Rust only.
+    pub fn max_buffer_length_from_utf16_with_unmappable_policy(
+        &self,
+        u16_length: usize,
+        policy: UnmappablePolicy,
+    ) -> Option<usize> {
+        let without_replacement = self.max_buffer_length_from_utf16_without_replacement(u16_length);
+        if self.encoding().can_encode_everything() {
+            return without_replacement;
+        }
+        let (length, ascii) = policy.longest_replacement();
+        checked_add_opt(
+            without_replacement,
+            self.replacement_buffer_length(length, ascii),
+        )
+    }
+
+    /// Incrementally encode into byte stream from UTF-16 with unpaired
+    /// surrogates in the range 0xDC00 to 0xDCFF turned back into the bytes
+    /// they escape and with unmappable characters replaced with HTML
+    /// (decimal) numeric character references.
+    ///
+    /// This is the inverse of
+    /// [`Encoding::decode_to_utf16_with_surrogate_escapes()`][1]: An unpaired
+    /// surrogate whose value is 0xDC00 plus a byte value is written to the
+    /// output as that byte as-is. Other unpaired surrogates are treated as
+    /// the REPLACEMENT CHARACTER as usual. Surrogate pairs must not be split
+    /// across input buffer boundaries, because a low surrogate at the start
+    /// of `src` is treated as unpaired.
+    ///
+    /// The escaped bytes are written without regard to the state of the
+    /// ISO-2022-JP encoder.
+    ///
+    /// [1]: struct.Encoding.html#method.decode_to_utf16_with_surrogate_escapes
+    ///
+    /// See the documentation of the struct for documentation for `encode_*`
+    /// methods collectively.
+    ///
+    /// Available to Rust only.
+    pub fn encode_from_utf16_with_surrogate_escapes(
+        &mut self,
+        src: &[u16],
+        dst: &mut [u8],
+        last: bool,
+    ) -> (CoderResult, usize, usize, bool) {
+        let mut had_unmappables = false;
+        let mut total_read = 0usize;
+        let mut total_written = 0usize;
+        loop {
+            let mut segment_end = total_read;
+            while segment_end < src.len() {
+                let unit = src[segment_end];
+                if (unit & 0xFF00) == 0xDC00
+                    && (segment_end == 0 || (src[segment_end - 1] & 0xFC00) != 0xD800)
+                {
+                    break;
+                }
+                segment_end += 1;
+            }
+            let (result, read, written, replaced) = self.encode_from_utf16(
+                &src[total_read..segment_end],
+                &mut dst[total_written..],
+                last && segment_end == src.len(),
+            );
+            total_read += read;
+            total_written += written;
+            had_unmappables |= replaced;
+            if result == CoderResult::OutputFull || total_read == src.len() {
+                return (result, total_read, total_written, had_unmappables);
+            }
+            debug_assert_eq!(total_read, segment_end);
+            if total_written == dst.len() {
+                return (
+                    CoderResult::OutputFull,
+                    total_read,
+                    total_written,
+                    had_unmappables,
+                );
+            }
+            dst[total_written] = src[total_read] as u8;
+            total_written += 1;
+            total_read += 1;
+        }
+    }
+}
+
+/// Removes the leading and trailing whitespace that `Encoding::for_label()`
+/// ignores.
+fn trim_label_whitespace(label: &[u8]) -> &[u8] {
+    let is_whitespace = |b: &u8| b"\t\n\x0C\r ".contains(b);
+    let start = label.iter().position(|b| !is_whitespace(b)).unwrap_or(label.len());
+    let end = label.iter().rposition(|b| !is_whitespace(b)).map_or(start, |i| i + 1);
+    &label[start..end]
+}
+
+/// ASCII-lowercases a label and removes separators and leading zeros of
+/// numbers for `Encoding::suggest_labels()`.
+fn normalize_label_for_suggestion(label: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(label.len());
+    let mut at_number_start = true;
+    for (i, &byte) in label.iter().enumerate() {
+        if byte.is_ascii_digit() {
+            let at_number_end = match label.get(i + 1) {
+                Some(next) => !next.is_ascii_digit(),
+                None => true,
+            };
+            if byte == b'0' && at_number_start && !at_number_end {
+                continue;
+            }
+            at_number_start = false;
+            normalized.push(byte);
+            continue;
+        }
+        at_number_start = true;
+        match byte {
+            b'\t' | b'\n' | b'\x0C' | b'\r' | b' ' | b'-' | b'_' | b'.' | b':' => {}
+            _ => normalized.push(byte.to_ascii_lowercase()),
+        }
+    }
+    normalized
+}
+
+/// Computes the Levenshtein distance between two byte strings.
+fn edit_distance(a: &[u8], b: &[u8]) -> usize {
+    let mut row: Vec<usize> = (0..b.len() + 1).collect();
+    for (i, &byte_a) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &byte_b) in b.iter().enumerate() {
+            let substitution = diagonal + if byte_a == byte_b { 0 } else { 1 };
+            diagonal = row[j + 1];
+            row[j + 1] = std::cmp::min(substitution, std::cmp::min(row[j], diagonal) + 1);
+        }
+    }
+    row[b.len()]
 }
 
 /// Format an unmappable as NCR without heap allocation.
@@ -5334,6 +8367,999 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_decode_without_bom_handling() {
+        assert_eq!(
+            &SHIFT_JIS.try_decode_without_bom_handling(b"abc\x82\xA0").unwrap()[..],
+            "abc\u{3042}"
+        );
+        let err = SHIFT_JIS
+            .try_decode_without_bom_handling(b"abc\x82\xA0\x82\x20")
+            .unwrap_err();
+        assert_eq!(err.encoding(), SHIFT_JIS);
+        assert_eq!(err.offset(), 5);
+        assert_eq!(err.malformed_sequence(), b"\x82");
+        assert_eq!(format!("{}", err), "invalid Shift_JIS at byte 5");
+        let err = WINDOWS_1257
+            .try_decode_without_bom_handling(b"abc\x80\xA1\xE4")
+            .unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(err.malformed_sequence(), b"\xA1");
+        let err = UTF_8
+            .try_decode_without_bom_handling(b"ab\xC3\xA4\xF0\x9F\x92a")
+            .unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(err.malformed_sequence(), b"\xF0\x9F\x92");
+        let err = UTF_8
+            .try_decode_without_bom_handling(b"ab\xE2\x82")
+            .unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert_eq!(err.malformed_sequence(), b"\xE2\x82");
+        let err = GB18030
+            .try_decode_without_bom_handling(b"\x81\x30\x81\x30\x81\x30\x81")
+            .unwrap_err();
+        assert_eq!(err.offset(), 4);
+        assert_eq!(err.malformed_sequence(), b"\x81\x30\x81");
+        let err = ISO_2022_JP
+            .try_decode_without_bom_handling(b"ab\x1B$B\x1B(Bc")
+            .unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert_eq!(err.malformed_sequence(), b"\x1B$B");
+        let err = ISO_2022_JP
+            .try_decode_without_bom_handling(b"ab\x1B$")
+            .unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert_eq!(err.malformed_sequence(), b"\x1B");
+        let err = UTF_16LE
+            .try_decode_without_bom_handling(b"a\x00\x00\xD8b\x00")
+            .unwrap_err();
+        assert_eq!(err.offset(), 2);
+        assert_eq!(err.malformed_sequence(), b"\x00\xD8");
+    }
+
+    #[test]
+    fn test_decode_with_malformed_spans() {
+        let (cow, encoding, spans) = WINDOWS_1257.decode_with_malformed_spans(b"abc");
+        assert_eq!(&cow[..], "abc");
+        assert_eq!(encoding, WINDOWS_1257);
+        assert!(spans.is_empty());
+
+        let (cow, encoding, spans) =
+            WINDOWS_1257.decode_with_malformed_spans(b"\xEF\xBB\xBFa\xFFb\xE2\x82\xACc\xC3");
+        assert_eq!(&cow[..], "a\u{FFFD}b\u{20AC}c\u{FFFD}");
+        assert_eq!(encoding, UTF_8);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].input(), 4..5);
+        assert_eq!(spans[0].output(), 1..4);
+        assert_eq!(spans[1].input(), 10..11);
+        assert_eq!(spans[1].output(), 9..12);
+        assert_eq!(&cow[spans[1].output()], "\u{FFFD}");
+    }
+
+    #[test]
+    fn test_decode_to_string_with_malformed_spans_across_buffers() {
+        let mut decoder = SHIFT_JIS.new_decoder_without_bom_handling();
+        let mut string = String::with_capacity(40);
+        let mut spans = Vec::new();
+        let (result, read, had_errors) = decoder.decode_to_string_with_malformed_spans(
+            b"ab\x82",
+            &mut string,
+            false,
+            &mut spans,
+        );
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, 3);
+        assert!(!had_errors);
+        let (result, read, had_errors) = decoder.decode_to_string_with_malformed_spans(
+            b"\xFFc\x82",
+            &mut string,
+            true,
+            &mut spans,
+        );
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, 3);
+        assert!(had_errors);
+        assert_eq!(string, "ab\u{FFFD}c\u{FFFD}");
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].input(), 2..4);
+        assert_eq!(spans[0].output(), 2..5);
+        assert_eq!(spans[1].input(), 5..6);
+        assert_eq!(spans[1].output(), 6..9);
+    }
+
+    #[test]
+    fn test_decode_with_malformed_policy() {
+        let input = b"a\x82\xFFb\xE2\x82\xACc\xC3";
+        let (cow, encoding, had_errors) =
+            UTF_8.decode_with_malformed_policy(input, MalformedPolicy::Replace);
+        assert_eq!(&cow[..], "a\u{FFFD}\u{FFFD}b\u{20AC}c\u{FFFD}");
+        assert_eq!(encoding, UTF_8);
+        assert!(had_errors);
+        let (cow, _, _) = UTF_8.decode_with_malformed_policy(input, MalformedPolicy::Skip);
+        assert_eq!(&cow[..], "ab\u{20AC}c");
+        let (cow, _, _) = UTF_8.decode_with_malformed_policy(input, MalformedPolicy::Escape);
+        assert_eq!(&cow[..], "a\\x82\\xFFb\u{20AC}c\\xC3");
+        let (cow, _, _) =
+            UTF_8.decode_with_malformed_policy(input, MalformedPolicy::Substitute("<?>"));
+        assert_eq!(&cow[..], "a<?><?>b\u{20AC}c<?>");
+        let (cow, _, had_errors) =
+            SHIFT_JIS.decode_with_malformed_policy(b"abc", MalformedPolicy::Escape);
+        match cow {
+            Cow::Borrowed(s) => assert_eq!(s, "abc"),
+            Cow::Owned(_) => unreachable!(),
+        }
+        assert!(!had_errors);
+    }
+
+    #[test]
+    fn test_decode_with_malformed_handler() {
+        let mut seen = Vec::new();
+        let (cow, encoding, had_errors) = WINDOWS_1252.decode_with_malformed_handler(
+            b"\xFF\xFEa\x00\x00\xD8b\x00",
+            |malformed, dst| {
+                seen.push(malformed.to_vec());
+                dst.push_str("[bad]");
+            },
+        );
+        assert_eq!(&cow[..], "a[bad]b");
+        assert_eq!(encoding, UTF_16LE);
+        assert!(had_errors);
+        assert_eq!(seen, vec![b"\x00\xD8".to_vec()]);
+    }
+
+    fn decode_split_with_malformed_handler(
+        encoding: &'static Encoding,
+        bytes: &[u8],
+        split: usize,
+    ) -> String {
+        let mut decoder = encoding.new_decoder();
+        let mut string = String::with_capacity(1024);
+        let mut handler = |malformed: &[u8], dst: &mut String| {
+            MalformedPolicy::Escape.handle(malformed, dst)
+        };
+        let (result, read, _) = decoder.decode_to_string_with_malformed_handler(
+            &bytes[..split],
+            &mut string,
+            false,
+            &mut handler,
+        );
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, split);
+        let (result, read, _) = decoder.decode_to_string_with_malformed_handler(
+            &bytes[split..],
+            &mut string,
+            true,
+            &mut handler,
+        );
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, bytes.len() - split);
+        string
+    }
+
+    #[test]
+    fn test_decoder_with_malformed_handler_split() {
+        let cases: [(&Encoding, &[u8]); 6] = [
+            (UTF_8, b"a\xE3\x81b\xF0\x90\x80\xFFc\xE3\x81"),
+            (GB18030, b"\x81\x30\x81x\x81\x30y\xFF\x81\x30\x81"),
+            (SHIFT_JIS, b"\x82\x20\x82\xA0\xFF\x82"),
+            (EUC_JP, b"\x8F\xA1\x20\x8E\x20\xA4\xA2\x8F\xA1"),
+            (ISO_2022_JP, b"\x1B$B\x24\x22\x1B(Bx\x1B$x\x1B(\x1B"),
+            (UTF_16LE, b"a\x00\x00\xD8b\x00\x00\xDC\x00"),
+        ];
+        for &(encoding, bytes) in cases.iter() {
+            let (expect, _, had_errors) =
+                encoding.decode_with_malformed_policy(bytes, MalformedPolicy::Escape);
+            assert!(had_errors);
+            for split in 0..bytes.len() + 1 {
+                assert_eq!(
+                    decode_split_with_malformed_handler(encoding, bytes, split),
+                    expect,
+                    "{} split at {}",
+                    encoding.name(),
+                    split
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_decoder_with_malformed_handler_calls() {
+        let mut decoder = UTF_8.new_decoder();
+        let mut string = String::with_capacity(100);
+        let mut seen = Vec::new();
+        for chunk in [&b"a\xF0"[..], &b"\x90"[..], &b"\x80"[..], &b"b\xC3"[..]].iter() {
+            let (result, _, _) =
+                decoder.decode_to_string_with_malformed_handler(chunk, &mut string, false, |m, _| {
+                    seen.push(m.to_vec())
+                });
+            assert_eq!(result, CoderResult::InputEmpty);
+        }
+        let (result, _, had_errors) =
+            decoder.decode_to_string_with_malformed_handler(b"", &mut string, true, |m, dst| {
+                seen.push(m.to_vec());
+                dst.push('?');
+            });
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert!(had_errors);
+        assert_eq!(string, "ab?");
+        assert_eq!(seen, vec![b"\xF0\x90\x80".to_vec(), b"\xC3".to_vec()]);
+    }
+
+    fn round_trip_surrogate_escapes(encoding: &'static Encoding, bytes: &[u8], expect: &[u16]) {
+        let (utf16, actual_encoding, had_errors) =
+            encoding.decode_to_utf16_with_surrogate_escapes(bytes);
+        assert_eq!(&utf16[..], expect);
+        assert_eq!(actual_encoding, encoding);
+        assert!(had_errors);
+        let mut encoder = encoding.new_encoder();
+        let mut output = [0u8; 32];
+        let (result, read, written, _) =
+            encoder.encode_from_utf16_with_surrogate_escapes(&utf16[..], &mut output[..], true);
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, utf16.len());
+        assert_eq!(&output[..written], bytes);
+    }
+
+    #[test]
+    fn test_surrogate_escapes() {
+        round_trip_surrogate_escapes(
+            WINDOWS_1253,
+            b"a\xAA\xE1\xFF",
+            &[0x0061, 0xDCAA, 0x03B1, 0xDCFF],
+        );
+        round_trip_surrogate_escapes(
+            UTF_8,
+            b"\xF0\x9F\x92\xA9\xF0\x9F\x92a\xC0",
+            &[0xD83D, 0xDCA9, 0xDCF0, 0xDC9F, 0xDC92, 0x0061, 0xDCC0],
+        );
+        round_trip_surrogate_escapes(
+            SHIFT_JIS,
+            b"\x82\xA0\x82\xFF\xA0",
+            &[0x3042, 0xDC82, 0xDCFF, 0xDCA0],
+        );
+    }
+
+    fn decode_split_with_surrogate_escapes(
+        encoding: &'static Encoding,
+        bytes: &[u8],
+        split: usize,
+        output_length: usize,
+    ) -> Vec<u16> {
+        let mut decoder = encoding.new_decoder();
+        let mut output = [0u16; 1024];
+        let mut vec = Vec::new();
+        let mut total_read = 0usize;
+        for &(end, last) in [(split, false), (bytes.len(), true)].iter() {
+            loop {
+                let (result, read, written, _) = decoder.decode_to_utf16_with_surrogate_escapes(
+                    &bytes[total_read..end],
+                    &mut output[..output_length],
+                    last,
+                );
+                total_read += read;
+                vec.extend_from_slice(&output[..written]);
+                if result == CoderResult::InputEmpty {
+                    break;
+                }
+            }
+        }
+        assert_eq!(total_read, bytes.len());
+        vec
+    }
+
+    #[test]
+    fn test_decoder_with_surrogate_escapes_split() {
+        let cases: [(&Encoding, &[u8]); 5] = [
+            (UTF_8, b"a\xE3\x81b\xF0\x90\x80\xFFc\xE3\x81"),
+            (GB18030, b"\x81\x30\x81x\x81\x30y\xFF\x81\x30\x81"),
+            (SHIFT_JIS, b"\x82\x20\x82\xA0\xFF\x82"),
+            (ISO_2022_JP, b"\x1B$B\x24\x22\x1B(Bx\x1B$x\x1B(\x1B"),
+            (WINDOWS_1253, b"a\xAA\xD2b"),
+        ];
+        for &(encoding, bytes) in cases.iter() {
+            let (expect, _, had_errors) = encoding.decode_to_utf16_with_surrogate_escapes(bytes);
+            assert!(had_errors);
+            for split in 0..bytes.len() + 1 {
+                assert_eq!(
+                    decode_split_with_surrogate_escapes(encoding, bytes, split, 1024),
+                    expect,
+                    "{} split at {}",
+                    encoding.name(),
+                    split
+                );
+                assert_eq!(
+                    decode_split_with_surrogate_escapes(encoding, bytes, split, 2),
+                    expect,
+                    "{} split at {} with short output",
+                    encoding.name(),
+                    split
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_encode_from_utf16_with_surrogate_escapes_output_full() {
+        let mut encoder = WINDOWS_1252.new_encoder();
+        let mut output = [0u8; 32];
+        let (result, read, written, had_unmappables) = encoder
+            .encode_from_utf16_with_surrogate_escapes(&[0xDC81, 0x3042], &mut output[..10], true);
+        assert_eq!(result, CoderResult::OutputFull);
+        assert_eq!(read, 1);
+        assert_eq!(written, 1);
+        assert!(!had_unmappables);
+        assert_eq!(output[0], 0x81);
+        let (result, read, written, had_unmappables) = encoder
+            .encode_from_utf16_with_surrogate_escapes(&[0x3042, 0xDD00], &mut output[..], true);
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, 2);
+        assert_eq!(&output[..written], b"&#12354;&#65533;");
+        assert!(had_unmappables);
+    }
+
+    #[test]
+    fn test_encode_with_unmappable_policy() {
+        let (cow, encoding, had_errors) =
+            WINDOWS_1252.encode_with_unmappable_policy("a\u{3042}b", UnmappablePolicy::Ncr);
+        assert_eq!(&cow[..], b"a&#12354;b");
+        assert_eq!(encoding, WINDOWS_1252);
+        assert!(had_errors);
+        let (cow, _, had_errors) = WINDOWS_1252.encode_with_unmappable_policy(
+            "a\u{3042}\u{1F4A9}b",
+            UnmappablePolicy::Substitute("?"),
+        );
+        assert_eq!(&cow[..], b"a??b");
+        assert!(had_errors);
+        let (cow, _, had_errors) = WINDOWS_1252.encode_with_unmappable_policy(
+            "\u{E4}\u{3042}",
+            UnmappablePolicy::Substitute("\u{FC}\u{3044}"),
+        );
+        assert_eq!(&cow[..], b"\xE4\xFC&#12356;");
+        assert!(had_errors);
+        let (cow, _, had_errors) =
+            WINDOWS_1252.encode_with_unmappable_policy("abc", UnmappablePolicy::Substitute("?"));
+        match cow {
+            Cow::Borrowed(b) => assert_eq!(b, b"abc"),
+            Cow::Owned(_) => unreachable!(),
+        }
+        assert!(!had_errors);
+        let (cow, _, _) = ISO_2022_JP
+            .encode_with_unmappable_policy("a\u{1F4A9}b", UnmappablePolicy::Substitute("\u{3013}"));
+        assert_eq!(&cow[..], b"a\x1B$B\".\x1B(Bb");
+    }
+
+    #[test]
+    fn test_encode_with_unmappable_escape_policies() {
+        let input = "a\u{3042}\u{1F4A9}b";
+        let (cow, _, _) =
+            WINDOWS_1252.encode_with_unmappable_policy(input, UnmappablePolicy::HexNcr);
+        assert_eq!(&cow[..], b"a&#x3042;&#x1F4A9;b");
+        let (cow, _, _) =
+            WINDOWS_1252.encode_with_unmappable_policy(input, UnmappablePolicy::JavaScript);
+        assert_eq!(&cow[..], b"a\\u3042\\uD83D\\uDCA9b");
+        let (cow, _, _) = WINDOWS_1252.encode_with_unmappable_policy(input, UnmappablePolicy::Css);
+        assert_eq!(&cow[..], b"a\\3042 \\1F4A9 b");
+        let (cow, _, _) =
+            WINDOWS_1252.encode_with_unmappable_policy(input, UnmappablePolicy::PercentEncodedNcr);
+        assert_eq!(&cow[..], b"a%26%2312354%3B%26%23128169%3Bb");
+        // The backslash is not in JIS X 0201 Roman.
+        let (cow, _, _) = ISO_2022_JP
+            .encode_with_unmappable_policy("\u{A5}\u{1F4A9}", UnmappablePolicy::Css);
+        assert_eq!(&cow[..], b"\x1B(J\\\x1B(B\\1F4A9 ");
+    }
+
+    #[test]
+    fn test_encode_with_best_fit() {
+        let (cow, _, had_errors) = KOI8_R.encode_with_unmappable_policy(
+            "\u{201C}Caf\u{E9}\u{201D} \u{2014} \u{FF21}\u{FF22} \u{C6}\u{1F4A9}",
+            UnmappablePolicy::BestFit,
+        );
+        assert_eq!(&cow[..], &b"\"Cafe\" - AB AE&#128169;"[..]);
+        assert!(had_errors);
+        // Mappable characters are never approximated.
+        let (cow, _, _) =
+            WINDOWS_1252.encode_with_unmappable_policy("\u{E9}\u{11B}", UnmappablePolicy::BestFit);
+        assert_eq!(&cow[..], b"\xE9e");
+        let (cow, _, _) =
+            IBM866.encode_with_unmappable_policy("\u{4E00}", UnmappablePolicy::BestFit);
+        assert_eq!(&cow[..], b"&#19968;");
+        let (cow, _, _) =
+            ISO_2022_JP.encode_with_unmappable_policy("\u{A5}\u{2216}", UnmappablePolicy::BestFit);
+        assert_eq!(&cow[..], b"\x1B(J\\\x1B(B\\");
+    }
+
+    #[test]
+    fn test_max_buffer_length_with_unmappable_policy() {
+        let policies = [
+            UnmappablePolicy::Ncr,
+            UnmappablePolicy::HexNcr,
+            UnmappablePolicy::JavaScript,
+            UnmappablePolicy::Css,
+            UnmappablePolicy::PercentEncodedNcr,
+            UnmappablePolicy::Substitute("\u{3013}"),
+            UnmappablePolicy::BestFit,
+        ];
+        for encoding in [WINDOWS_1252, SHIFT_JIS, ISO_2022_JP].iter() {
+            for policy in policies.iter() {
+                let mut encoder = encoding.new_encoder();
+                let length = encoder
+                    .max_buffer_length_from_utf8_with_unmappable_policy(4, *policy)
+                    .unwrap();
+                let mut output = vec![0u8; length];
+                let (result, read, _, had_unmappables) = encoder
+                    .encode_from_utf8_with_unmappable_handler(
+                        "\u{1F4A9}",
+                        &mut output[..],
+                        true,
+                        |c, dst| policy.handle(c, dst),
+                    );
+                assert_eq!(result, CoderResult::InputEmpty);
+                assert_eq!(read, 4);
+                assert!(had_unmappables);
+                let mut encoder = encoding.new_encoder();
+                let length = encoder
+                    .max_buffer_length_from_utf16_with_unmappable_policy(2, *policy)
+                    .unwrap();
+                let mut output = vec![0u8; length];
+                let (result, read, _, had_unmappables) = encoder
+                    .encode_from_utf16_with_unmappable_handler(
+                        &[0xD83D, 0xDCA9],
+                        &mut output[..],
+                        true,
+                        |c, dst| policy.handle(c, dst),
+                    );
+                assert_eq!(result, CoderResult::InputEmpty);
+                assert_eq!(read, 2);
+                assert!(had_unmappables);
+            }
+        }
+        assert_eq!(
+            UTF_8
+                .new_encoder()
+                .max_buffer_length_from_utf8_with_unmappable_policy(4, UnmappablePolicy::Css),
+            UTF_8.new_encoder().max_buffer_length_from_utf8_without_replacement(4)
+        );
+    }
+
+    #[test]
+    fn test_can_encode() {
+        assert!(ISO_2022_JP.can_encode("abc\u{3042}\u{FF71}\u{A5}"));
+        assert!(!ISO_2022_JP.can_encode("abc\u{1B}"));
+        assert!(EUC_KR.can_encode("\u{D55C}\u{AE00}"));
+        assert!(!EUC_KR.can_encode("\u{0E01}"));
+        assert!(WINDOWS_1251.can_encode(""));
+        assert!(UTF_16LE.can_encode("\u{1F4A9}"));
+        assert_eq!(WINDOWS_1251.encodable_up_to("ab\u{0416}\u{3042}c"), 4);
+        assert_eq!(WINDOWS_1251.encodable_up_to("ab\u{0416}"), 4);
+        let unmappables: Vec<(usize, char)> =
+            SHIFT_JIS.unmappables("a\u{1F4A9}\u{3042}\u{0E01}b\u{0E02}").collect();
+        assert_eq!(
+            unmappables,
+            vec![(1, '\u{1F4A9}'), (8, '\u{0E01}'), (12, '\u{0E02}')]
+        );
+        assert_eq!(UTF_8.unmappables("\u{1F4A9}").next(), None);
+        let unmappables: Vec<(usize, char)> = ISO_2022_JP.unmappables("a\u{1B}b").collect();
+        assert_eq!(unmappables, vec![(1, '\u{1B}')]);
+    }
+
+    #[test]
+    fn test_unmappables_agree_with_encoder() {
+        let mut buffer = [0u8; 16];
+        for encoding in ENCODINGS.iter() {
+            let output_encoding = encoding.output_encoding();
+            let mut encoder = output_encoding.new_encoder();
+            for u in (0u32..0x10000).chain(0x20000..0x2F8B0) {
+                let c = match ::std::char::from_u32(u) {
+                    Some(c) => c,
+                    None => continue,
+                };
+                let mut utf8 = [0u8; 4];
+                let string = c.encode_utf8(&mut utf8[..]);
+                let (result, _, _) =
+                    encoder.encode_from_utf8_without_replacement(string, &mut buffer[..], true);
+                let unmappable = match result {
+                    EncoderResult::InputEmpty => false,
+                    EncoderResult::Unmappable(_) => true,
+                    EncoderResult::OutputFull => unreachable!(),
+                };
+                assert_eq!(
+                    output_encoding.unmappables(string).next().is_some(),
+                    unmappable,
+                    "{} U+{:04X}",
+                    output_encoding.name(),
+                    u
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_windows_code_page() {
+        assert_eq!(Encoding::for_windows_code_page(1252), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_windows_code_page(28591), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_windows_code_page(932), Some(SHIFT_JIS));
+        assert_eq!(Encoding::for_windows_code_page(50220), Some(ISO_2022_JP));
+        assert_eq!(Encoding::for_windows_code_page(51932), Some(EUC_JP));
+        assert_eq!(Encoding::for_windows_code_page(51949), Some(EUC_KR));
+        assert_eq!(Encoding::for_windows_code_page(28601), Some(WINDOWS_874));
+        assert_eq!(Encoding::for_windows_code_page(65001), Some(UTF_8));
+        assert_eq!(Encoding::for_windows_code_page(437), None);
+        assert_eq!(Encoding::for_windows_code_page(0), None);
+        assert_eq!(WINDOWS_1252.windows_code_page(), Some(1252));
+        assert_eq!(ISO_2022_JP.windows_code_page(), Some(50221));
+        assert_eq!(UTF_16BE.windows_code_page(), Some(1201));
+        assert_eq!(REPLACEMENT.windows_code_page(), None);
+        assert_eq!(X_USER_DEFINED.windows_code_page(), None);
+        for &(encoding, code_page) in CODE_PAGES_BY_ENCODING.iter() {
+            assert_eq!(Encoding::for_windows_code_page(code_page), Some(encoding));
+        }
+    }
+
+    #[test]
+    fn test_for_platform_name() {
+        assert_eq!(
+            Encoding::for_platform_name(b"Windows-31J"),
+            Some(LabelMatch::Exact(SHIFT_JIS))
+        );
+        assert_eq!(
+            Encoding::for_platform_name(b"x-SJIS_0213"),
+            Some(LabelMatch::Approximate(SHIFT_JIS))
+        );
+        assert_eq!(Encoding::for_platform_name(b"MS949"), Some(LabelMatch::Exact(EUC_KR)));
+        assert_eq!(
+            Encoding::for_platform_name(b"x-IBM874"),
+            Some(LabelMatch::Approximate(WINDOWS_874))
+        );
+        assert_eq!(
+            Encoding::for_platform_name(b" ibm-943_P15A-2003 "),
+            Some(LabelMatch::Exact(SHIFT_JIS))
+        );
+        assert_eq!(Encoding::for_platform_name(b"cp932"), Some(LabelMatch::Exact(SHIFT_JIS)));
+        assert_eq!(
+            Encoding::for_platform_name(b"sjis_2004"),
+            Some(LabelMatch::Approximate(SHIFT_JIS))
+        );
+        assert_eq!(Encoding::for_platform_name(b"euc_jp"), Some(LabelMatch::Exact(EUC_JP)));
+        assert_eq!(
+            Encoding::for_platform_name(b"ISO-8859-1"),
+            Some(LabelMatch::Approximate(WINDOWS_1252))
+        );
+        assert_eq!(
+            Encoding::for_platform_name(b"windows-1252"),
+            Some(LabelMatch::Exact(WINDOWS_1252))
+        );
+        assert_eq!(
+            Encoding::for_platform_name(b"cseucpkdfmtjapanese"),
+            Some(LabelMatch::Exact(EUC_JP))
+        );
+        assert_eq!(Encoding::for_platform_name(b"ISO-2022-KR"), None);
+        assert_eq!(Encoding::for_platform_name(b"EBCDIC-US"), None);
+        assert_eq!(Encoding::for_platform_name(b""), None);
+        assert!(!Encoding::for_platform_name(b"utf-16").unwrap().is_exact());
+        assert_eq!(
+            Encoding::for_platform_name(b"UTF_16_BE").unwrap().encoding(),
+            UTF_16BE
+        );
+        for pair in PLATFORM_NAMES_SORTED.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+        for &(name, label, _) in PLATFORM_NAMES_SORTED.iter() {
+            assert!(name.len() <= LONGEST_PLATFORM_NAME_LENGTH);
+            assert!(Encoding::for_label_no_replacement(label.as_bytes()).is_some());
+        }
+    }
+
+    #[test]
+    fn test_for_database_names() {
+        assert_eq!(Encoding::for_mysql_charset(b"latin1"), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_mysql_charset(b"utf8mb4"), Some(UTF_8));
+        assert_eq!(Encoding::for_mysql_charset(b"sjis"), Some(SHIFT_JIS));
+        assert_eq!(Encoding::for_mysql_charset(b"ujis"), Some(EUC_JP));
+        assert_eq!(Encoding::for_mysql_charset(b"euckr"), Some(EUC_KR));
+        assert_eq!(Encoding::for_mysql_charset(b"GBK"), Some(GBK));
+        assert_eq!(Encoding::for_mysql_charset(b"big5"), Some(BIG5));
+        assert_eq!(Encoding::for_mysql_charset(b"koi8r"), Some(KOI8_R));
+        assert_eq!(Encoding::for_mysql_charset(b"cp1251"), Some(WINDOWS_1251));
+        assert_eq!(Encoding::for_mysql_charset(b"utf16"), Some(UTF_16BE));
+        assert_eq!(Encoding::for_mysql_charset(b"binary"), None);
+        assert_eq!(Encoding::for_mysql_charset(b"utf32"), None);
+        assert_eq!(Encoding::for_postgresql_encoding(b"WIN1252"), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_postgresql_encoding(b"SQL_ASCII"), None);
+        assert_eq!(Encoding::for_postgresql_encoding(b"EUC_JIS_2004"), Some(EUC_JP));
+        assert_eq!(Encoding::for_postgresql_encoding(b"LATIN9"), Some(ISO_8859_15));
+        assert_eq!(Encoding::for_postgresql_encoding(b"latin1"), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_postgresql_encoding(b"Shift-JIS"), Some(SHIFT_JIS));
+        assert_eq!(Encoding::for_postgresql_encoding(b"UNICODE"), Some(UTF_8));
+        assert_eq!(Encoding::for_postgresql_encoding(b"EUC_TW"), None);
+        assert_eq!(Encoding::for_postgresql_encoding(b"SHIFT_JIS_2004_AND_MORE"), None);
+        for names in [&MYSQL_CHARSETS_SORTED[..], &POSTGRESQL_ENCODINGS_SORTED[..]].iter() {
+            for pair in names.windows(2) {
+                assert!(pair[0].0 < pair[1].0);
+            }
+            for &(name, label) in names.iter() {
+                assert!(name.len() <= LONGEST_DATABASE_NAME_LENGTH);
+                assert!(Encoding::for_label_no_replacement(label.as_bytes()).is_some());
+            }
+        }
+    }
+
+    #[test]
+    fn test_all_and_labels() {
+        let all = Encoding::all();
+        assert_eq!(all.len(), 40);
+        for pair in all.windows(2) {
+            assert!(pair[0].name() < pair[1].name());
+        }
+        let mut label_count = 0;
+        for encoding in all.iter() {
+            assert_eq!(
+                Encoding::for_label(encoding.name().as_bytes()),
+                Some(*encoding)
+            );
+            for label in encoding.labels() {
+                assert_eq!(Encoding::for_label(label.as_bytes()), Some(*encoding));
+                label_count += 1;
+            }
+        }
+        assert_eq!(label_count, LABELS_SORTED.len());
+        let mut labels: Vec<&str> = UTF_8.labels().collect();
+        labels.sort();
+        assert_eq!(labels, vec!["unicode-1-1-utf-8", "utf-8", "utf8"]);
+        assert_eq!(X_USER_DEFINED.labels().collect::<Vec<&str>>(), vec!["x-user-defined"]);
+    }
+
+    #[test]
+    fn test_suggest_labels() {
+        let suggestions = Encoding::suggest_labels(b"utf-8y", 3);
+        assert_eq!(suggestions[0].encoding(), UTF_8);
+        assert_eq!(suggestions[0].confidence(), 0.8);
+        let suggestions = Encoding::suggest_labels(b"shiftjis", 3);
+        assert_eq!(suggestions[0].encoding(), SHIFT_JIS);
+        assert_eq!(suggestions[0].confidence(), 1.0);
+        let suggestions = Encoding::suggest_labels(b"ISO8859_1", 3);
+        assert_eq!(suggestions[0].encoding(), WINDOWS_1252);
+        assert_eq!(suggestions[0].confidence(), 1.0);
+        let suggestions = Encoding::suggest_labels(b"Windows 01252", 1);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].label(), "windows-1252");
+        assert_eq!(suggestions[0].confidence(), 1.0);
+        let suggestions = Encoding::suggest_labels(b"windows-1259", 10);
+        assert!(suggestions.len() > 1);
+        for pair in suggestions.windows(2) {
+            assert!(pair[0].confidence() >= pair[1].confidence());
+            assert!(pair[0].encoding() != pair[1].encoding());
+        }
+        assert!(Encoding::suggest_labels(b"iso-2022-kr", 10)
+            .iter()
+            .all(|s| s.encoding() != REPLACEMENT));
+        assert!(Encoding::suggest_labels(b"qqqqqqqqqqqq", 10).is_empty());
+        assert!(Encoding::suggest_labels(b"", 10).is_empty());
+        assert!(Encoding::suggest_labels(b"utf-8", 0).is_empty());
+    }
+
+    #[test]
+    fn test_label_registry() {
+        let mut registry = LabelRegistry::new();
+        assert_eq!(registry.for_label(b"utf8mb4"), None);
+        assert!(registry.register(b"utf8mb4", UTF_8));
+        assert!(registry.register(b" CP65001\n", UTF_8));
+        assert!(registry.register(b"latin-1", WINDOWS_1252));
+        assert!(registry.register(b"sjis-open", SHIFT_JIS));
+        assert!(registry.register(b"MS_Kanji2", SHIFT_JIS));
+        assert!(registry.register(b"iso-2022-kr-ish", REPLACEMENT));
+        assert_eq!(registry.for_label(b"UTF8MB4"), Some(UTF_8));
+        assert_eq!(registry.for_label(b"\tcp65001 "), Some(UTF_8));
+        assert_eq!(registry.for_label(b"Latin-1"), Some(WINDOWS_1252));
+        assert_eq!(registry.for_label(b"ms_kanji2"), Some(SHIFT_JIS));
+        assert_eq!(registry.for_label(b"sjis-open"), Some(SHIFT_JIS));
+        assert_eq!(registry.for_label(b"sjis-open2"), None);
+        assert_eq!(registry.for_label(b"iso-2022-kr-ish"), Some(REPLACEMENT));
+        assert_eq!(registry.for_label_no_replacement(b"iso-2022-kr-ish"), None);
+        // Built-in labels stay authoritative.
+        assert!(!registry.register(b"Latin1", UTF_8));
+        assert!(!registry.register(b" \t", UTF_8));
+        assert_eq!(registry.for_label(b"latin1"), Some(WINDOWS_1252));
+        assert!(!registry.register(b"x-sjis", EUC_JP));
+        assert_eq!(registry.for_label(b"x-sjis"), Some(SHIFT_JIS));
+        assert!(registry.register(b"sjis-open", EUC_JP));
+        assert_eq!(registry.for_label(b"sjis-open"), Some(EUC_JP));
+        assert_eq!(registry.unregister(b"SJIS-open"), Some(EUC_JP));
+        assert_eq!(registry.unregister(b"sjis-open"), None);
+        assert_eq!(registry.for_label(b"sjis-open"), None);
+    }
+
+    #[test]
+    fn test_encoding_properties() {
+        assert!(WINDOWS_1252.is_single_byte());
+        assert!(X_USER_DEFINED.is_single_byte());
+        assert!(!UTF_8.is_single_byte());
+        assert!(!SHIFT_JIS.is_single_byte());
+        assert!(ISO_2022_JP.is_stateful());
+        assert!(!EUC_JP.is_stateful());
+        assert!(GBK.is_cjk());
+        assert!(EUC_KR.is_cjk());
+        assert!(ISO_2022_JP.is_cjk());
+        assert!(!KOI8_R.is_cjk());
+        assert!(!UTF_8.is_cjk());
+        assert_eq!(WINDOWS_1252.max_bytes_per_char(), 1);
+        assert_eq!(SHIFT_JIS.max_bytes_per_char(), 2);
+        assert_eq!(GBK.max_bytes_per_char(), 2);
+        assert_eq!(GB18030.max_bytes_per_char(), 4);
+        assert_eq!(UTF_16LE.max_bytes_per_char(), 4);
+        assert!(UTF_16BE.is_utf16());
+        assert!(UTF_16LE.is_utf16());
+        assert!(!UTF_8.is_utf16());
+        assert!(UTF_8.has_bom());
+        assert!(UTF_16LE.has_bom());
+        assert!(!GB18030.has_bom());
+        assert!(GB18030.is_superset_of(GBK));
+        assert!(GBK.is_superset_of(GB18030));
+        assert!(ISO_8859_8.is_superset_of(ISO_8859_8_I));
+        assert!(WINDOWS_1252.is_superset_of(WINDOWS_1252));
+        assert!(!WINDOWS_1252.is_superset_of(ISO_8859_15));
+        assert!(!UTF_8.is_superset_of(WINDOWS_1252));
+        assert!(!UTF_8.is_superset_of(REPLACEMENT));
+        for encoding in Encoding::all().iter() {
+            for other in Encoding::all().iter() {
+                if encoding != other && encoding.is_superset_of(other) {
+                    assert!(encoding.is_single_byte() || encoding.is_cjk());
+                    assert_eq!(encoding.is_single_byte(), other.is_single_byte());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_encoding_id() {
+        assert_eq!(std::mem::size_of::<EncodingId>(), 1);
+        for (i, encoding) in Encoding::all().iter().enumerate() {
+            let id = encoding.id();
+            assert_eq!(id as usize, i);
+            assert_eq!(id.encoding(), *encoding);
+            assert_eq!(EncodingId::from_u8(id as u8), Some(id));
+            assert_eq!(EncodingId::from(*encoding), id);
+            let converted: &'static Encoding = id.into();
+            assert_eq!(converted, *encoding);
+        }
+        assert_eq!(UTF_8.id(), EncodingId::Utf8);
+        assert_eq!(ISO_8859_8_I.id(), EncodingId::Iso8I);
+        assert_eq!(EncodingId::Windows1252.encoding(), WINDOWS_1252);
+        assert_eq!(EncodingId::from_u8(40), None);
+    }
+
+    #[test]
+    fn test_encoding_set() {
+        let mut set = EncodingSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(UTF_8));
+        assert!(!set.insert(UTF_8));
+        assert!(set.insert(SHIFT_JIS));
+        assert_eq!(set.len(), 2);
+        assert!(set.contains(UTF_8));
+        assert!(!set.contains(EUC_JP));
+        let other: EncodingSet = [SHIFT_JIS, EUC_JP].iter().cloned().collect();
+        assert_eq!(
+            (set | other).iter().collect::<Vec<&'static Encoding>>(),
+            vec![EUC_JP, SHIFT_JIS, UTF_8]
+        );
+        assert_eq!((set & other).iter().collect::<Vec<_>>(), vec![SHIFT_JIS]);
+        assert_eq!((set - other).iter().collect::<Vec<_>>(), vec![UTF_8]);
+        assert_eq!((set ^ other).len(), 2);
+        assert!((set & other).is_subset(&set));
+        assert!(set.is_superset(&(set & other)));
+        assert!(!set.is_disjoint(&other));
+        assert!((set - other).is_disjoint(&other));
+        assert_eq!(EncodingSet::from_bits(set.bits()), Some(set));
+        assert_eq!(EncodingSet::from_bits(1u64 << 40), None);
+        assert_eq!(EncodingSet::all().len(), Encoding::all().len());
+        assert_eq!(EncodingSet::all().iter().collect::<Vec<_>>(), Encoding::all().to_vec());
+        assert!(set.remove(UTF_8));
+        assert!(!set.remove(UTF_8));
+        assert_eq!(set.into_iter().collect::<Vec<_>>(), vec![SHIFT_JIS]);
+    }
+
+    #[test]
+    fn test_fallback_for_locale() {
+        assert_eq!(Encoding::fallback_for_locale("en-US"), WINDOWS_1252);
+        assert_eq!(Encoding::fallback_for_locale("ja"), SHIFT_JIS);
+        assert_eq!(Encoding::fallback_for_locale("ja-JP"), SHIFT_JIS);
+        assert_eq!(Encoding::fallback_for_locale("zh-CN"), GBK);
+        assert_eq!(Encoding::fallback_for_locale("zh"), GBK);
+        assert_eq!(Encoding::fallback_for_locale("zh_TW"), BIG5);
+        assert_eq!(Encoding::fallback_for_locale("zh-Hant"), BIG5);
+        assert_eq!(Encoding::fallback_for_locale("zh-HK"), BIG5);
+        assert_eq!(Encoding::fallback_for_locale("RU"), WINDOWS_1251);
+        assert_eq!(Encoding::fallback_for_locale("sah"), WINDOWS_1251);
+        assert_eq!(Encoding::fallback_for_locale("pl-PL"), ISO_8859_2);
+        assert_eq!(Encoding::fallback_for_locale("th"), WINDOWS_874);
+        assert_eq!(Encoding::fallback_for_locale(""), WINDOWS_1252);
+        for pair in LOCALE_FALLBACKS.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+    }
+
+    #[test]
+    fn test_fallback_for_tld() {
+        assert_eq!(Encoding::fallback_for_tld("jp"), Some(SHIFT_JIS));
+        assert_eq!(Encoding::fallback_for_tld(".JP"), Some(SHIFT_JIS));
+        assert_eq!(Encoding::fallback_for_tld("www.example.co.jp."), Some(SHIFT_JIS));
+        assert_eq!(Encoding::fallback_for_tld("ru"), Some(WINDOWS_1251));
+        assert_eq!(Encoding::fallback_for_tld("cn"), Some(GBK));
+        assert_eq!(Encoding::fallback_for_tld("tw"), Some(BIG5));
+        assert_eq!(Encoding::fallback_for_tld("com"), None);
+        assert_eq!(Encoding::fallback_for_tld("example.com"), None);
+        assert_eq!(Encoding::fallback_for_tld(""), None);
+        for pair in TLD_FALLBACKS.windows(2) {
+            assert!(pair[0].0 < pair[1].0);
+        }
+    }
+
+    #[test]
+    fn test_mib_enum() {
+        assert_eq!(Encoding::for_mib_enum(106), Some(UTF_8));
+        assert_eq!(Encoding::for_mib_enum(3), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_mib_enum(4), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_mib_enum(2252), Some(WINDOWS_1252));
+        assert_eq!(Encoding::for_mib_enum(12), Some(WINDOWS_1254));
+        assert_eq!(Encoding::for_mib_enum(1015), Some(UTF_16LE));
+        assert_eq!(Encoding::for_mib_enum(2025), Some(GBK));
+        assert_eq!(Encoding::for_mib_enum(37), Some(REPLACEMENT));
+        assert_eq!(Encoding::for_mib_enum(0), None);
+        assert_eq!(Encoding::for_mib_enum(2011), None);
+        assert_eq!(SHIFT_JIS.mib_enum(), Some(17));
+        assert_eq!(ISO_8859_8_I.mib_enum(), Some(85));
+        assert_eq!(WINDOWS_874.mib_enum(), Some(2109));
+        assert_eq!(X_MAC_CYRILLIC.mib_enum(), None);
+        assert_eq!(REPLACEMENT.mib_enum(), None);
+        for &(encoding, mib_enum) in MIB_ENUMS_BY_ENCODING.iter() {
+            assert_eq!(Encoding::for_mib_enum(mib_enum), Some(encoding));
+        }
+    }
+
+    #[test]
+    fn test_first_encodable() {
+        let candidates = [WINDOWS_1252, ISO_2022_JP, UTF_8];
+        assert_eq!(Encoding::first_encodable(&candidates, "abc"), Some(WINDOWS_1252));
+        assert_eq!(Encoding::first_encodable(&candidates, "\u{3042}"), Some(ISO_2022_JP));
+        assert_eq!(Encoding::first_encodable(&candidates, "\u{0E01}"), Some(UTF_8));
+        assert_eq!(Encoding::first_encodable(&candidates[..2], "\u{0E01}"), None);
+        assert_eq!(Encoding::first_encodable(&[UTF_16LE], "\u{0E01}"), Some(UTF_8));
+        assert_eq!(Encoding::first_encodable(&[], "abc"), None);
+        let candidates = [WINDOWS_1251, KOI8_R, UTF_8];
+        let (bytes, encoding) =
+            Encoding::encode_with_first_encodable(&candidates, "\u{0416}\u{2552}").unwrap();
+        assert_eq!(&bytes[..], b"\xF6\xA2");
+        assert_eq!(encoding, KOI8_R);
+        assert!(Encoding::encode_with_first_encodable(&[WINDOWS_1251], "\u{3042}").is_none());
+    }
+
+    #[test]
+    fn test_encode_with_unmappable_handler() {
+        let long = "x".repeat(100);
+        let (cow, _, had_errors) = EUC_KR.encode_with_unmappable_handler("\u{0E01}", |c, dst| {
+            assert_eq!(c, '\u{0E01}');
+            dst.push_str(&long);
+        });
+        assert_eq!(&cow[..], long.as_bytes());
+        assert!(had_errors);
+    }
+
+    #[test]
+    fn test_encode_with_unmappable_handler_calls_once() {
+        let long = "x".repeat(200);
+        let mut seen = Vec::new();
+        let (cow, _, had_errors) =
+            EUC_KR.encode_with_unmappable_handler("a\u{0E01}b\u{0E02}c", |c, dst| {
+                seen.push(c);
+                dst.push_str(&long);
+            });
+        assert_eq!(&cow[..], format!("a{}b{}c", long, long).as_bytes());
+        assert!(had_errors);
+        assert_eq!(seen, vec!['\u{0E01}', '\u{0E02}']);
+
+        let mut encoder = WINDOWS_1252.new_encoder();
+        let mut output = [0u8; 16];
+        let mut encoded = Vec::new();
+        let mut calls = 0;
+        let mut total_read = 0;
+        let input = "ab\u{3042}cd\u{3044}";
+        loop {
+            let (result, read, written, _) = encoder.encode_from_utf8_with_unmappable_handler(
+                &input[total_read..],
+                &mut output[..],
+                true,
+                |_, dst| {
+                    calls += 1;
+                    dst.push_str("[unmappable]");
+                },
+            );
+            total_read += read;
+            encoded.extend_from_slice(&output[..written]);
+            if result == CoderResult::InputEmpty {
+                break;
+            }
+        }
+        assert_eq!(&encoded[..], &b"ab[unmappable]cd[unmappable]"[..]);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_encode_with_unmappable_handler_ascii_replacement() {
+        // An ASCII replacement only needs as much space as it has bytes.
+        let mut encoder = WINDOWS_1252.new_encoder();
+        let mut output = [0u8; 4];
+        let (result, read, written, had_unmappables) =
+            encoder.encode_from_utf8_with_unmappable_handler(
+                "ab\u{3042}",
+                &mut output[..],
+                true,
+                |_, dst| dst.push('?'),
+            );
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, 5);
+        assert_eq!(&output[..written], b"ab?");
+        assert!(had_unmappables);
+        // A non-ASCII one needs room for numeric character references.
+        let mut encoder = WINDOWS_1252.new_encoder();
+        let (result, read, written, had_unmappables) =
+            encoder.encode_from_utf8_with_unmappable_handler(
+                "ab\u{3042}",
+                &mut output[..],
+                true,
+                |_, dst| dst.push('\u{E4}'),
+            );
+        assert_eq!(result, CoderResult::OutputFull);
+        assert_eq!(read, 2);
+        assert_eq!(written, 2);
+        assert!(!had_unmappables);
+    }
+
+    #[test]
+    fn test_encode_with_unmappable_handler_output_full() {
+        let mut encoder = WINDOWS_1252.new_encoder();
+        let mut output = [0u8; 32];
+        let mut calls = 0;
+        {
+            let mut handler = |_: char, dst: &mut String| {
+                calls += 1;
+                dst.push('?');
+            };
+            let (result, read, written, had_unmappables) = encoder
+                .encode_from_utf8_with_unmappable_handler(
+                    "ab\u{3042}",
+                    &mut output[..2],
+                    true,
+                    &mut handler,
+                );
+            assert_eq!(result, CoderResult::OutputFull);
+            assert_eq!(read, 2);
+            assert_eq!(written, 2);
+            assert!(!had_unmappables);
+            let (result, read, written, had_unmappables) = encoder
+                .encode_from_utf8_with_unmappable_handler(
+                    "\u{3042}",
+                    &mut output[..],
+                    true,
+                    &mut handler,
+                );
+            assert_eq!(result, CoderResult::InputEmpty);
+            assert_eq!(read, 3);
+            assert_eq!(&output[..written], b"?");
+            assert!(had_unmappables);
+        }
+        assert_eq!(calls, 1);
+        let mut encoder = WINDOWS_1252.new_encoder();
+        let (result, read, written, _) = encoder.encode_from_utf16_with_unmappable_handler(
+            &[0x61, 0xD83D, 0xDCA9, 0xDCA9],
+            &mut output[..],
+            true,
+            |_, dst| dst.push('?'),
+        );
+        assert_eq!(result, CoderResult::InputEmpty);
+        assert_eq!(read, 4);
+        assert_eq!(&output[..written], b"a??");
+    }
+
     #[test]
     fn test_encode_ascii_only_windows_1257_to_cow() {
         let (cow, encoding, had_errors) = WINDOWS_1257.encode("abc");
@@ -5616,6 +9642,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_decode_to_utf16_without_replacement_malformed() {
+        let mut output = [0u16; 20];
+        {
+            let mut decoder = SHIFT_JIS.new_decoder_without_bom_handling();
+            let (result, read, written) =
+                decoder.decode_to_utf16_without_replacement(b"a\x82\x20b", &mut output[..], true);
+            assert_eq!(result, DecoderResult::Malformed(1, 0));
+            assert_eq!(read, 2);
+            assert_eq!(written, 1);
+            assert_eq!(output[0], 0x0061);
+        }
+        {
+            let mut decoder = UTF_8.new_decoder_without_bom_handling();
+            let (result, read, written) =
+                decoder.decode_to_utf16_without_replacement(b"\xE2\x82a", &mut output[..], true);
+            assert_eq!(result, DecoderResult::Malformed(2, 0));
+            assert_eq!(read, 2);
+            assert_eq!(written, 0);
+        }
+        {
+            let mut decoder = WINDOWS_1257.new_decoder_without_bom_handling();
+            let (result, read, written) =
+                decoder.decode_to_utf16_without_replacement(b"\xE4\xA1", &mut output[..], true);
+            assert_eq!(result, DecoderResult::Malformed(1, 0));
+            assert_eq!(read, 2);
+            assert_eq!(written, 1);
+            assert_eq!(output[0], 0x00E4);
+        }
+    }
+
     #[cfg(feature = "serde")]
     #[test]
     fn test_serde() {
@@ -0,0 +1,428 @@
+// Copyright 2015-2016 Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! MIME type parsing for finding the `charset` of a `Content-Type`.
+//!
+//! This module implements the [_parse a MIME type_][1] algorithm of the MIME
+//! Sniffing Standard and the [_extract a MIME type_][2] and [_legacy extract
+//! an encoding_][3] algorithms of the Fetch Standard.
+//!
+//! The algorithms operate on strings. HTTP header values are bytes, which
+//! the Fetch Standard converts to strings by _isomorphic decoding_, i.e.
+//! by treating each byte as the code point of the same value. This is what
+//! [`mem::decode_latin1()`][4] does.
+//!
+//! [1]: https://mimesniff.spec.whatwg.org/#parse-a-mime-type
+//! [2]: https://fetch.spec.whatwg.org/#concept-header-extract-mime-type
+//! [3]: https://fetch.spec.whatwg.org/#legacy-extract-an-encoding
+//! [4]: ../mem/fn.decode_latin1.html
+
+use super::Encoding;
+
+/// A parsed MIME type.
+///
+/// The type, the subtype and the parameter names are lowercase. Parameter
+/// values are as given with the quoting removed.
+///
+/// Available to Rust only.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimeType {
+    type_name: String,
+    subtype: String,
+    parameters: Vec<(String, String)>,
+}
+
+impl MimeType {
+    /// Parses a MIME type such as `text/html; charset="Shift_JIS"`.
+    ///
+    /// Returns `None` if the string is not a valid MIME type. Parameters that
+    /// are not valid are skipped and when a parameter name occurs more than
+    /// once, the first occurrence wins.
+    ///
+    /// Available to Rust only.
+    pub fn parse(input: &str) -> Option<MimeType> {
+        let input = input.trim_matches(is_http_whitespace);
+        let slash = input.find('/')?;
+        let type_name = &input[..slash];
+        if type_name.is_empty() || !type_name.chars().all(is_http_token) {
+            return None;
+        }
+        let rest = &input[slash + 1..];
+        let subtype_len = rest.find(';').unwrap_or(rest.len());
+        #[allow(deprecated)]
+        let subtype = rest[..subtype_len].trim_right_matches(is_http_whitespace);
+        if subtype.is_empty() || !subtype.chars().all(is_http_token) {
+            return None;
+        }
+        let mut mime_type = MimeType {
+            type_name: type_name.to_ascii_lowercase(),
+            subtype: subtype.to_ascii_lowercase(),
+            parameters: Vec::new(),
+        };
+        let bytes = input.as_bytes();
+        let mut pos = slash + 1 + subtype_len;
+        while pos < input.len() {
+            // Skip the semicolon and any whitespace after it.
+            pos += 1;
+            while pos < input.len() && is_http_whitespace(bytes[pos] as char) {
+                pos += 1;
+            }
+            let name_len = input[pos..]
+                .find(&[';', '='][..])
+                .unwrap_or(input.len() - pos);
+            let name = input[pos..pos + name_len].to_ascii_lowercase();
+            pos += name_len;
+            if pos < input.len() {
+                if bytes[pos] == b';' {
+                    continue;
+                }
+                pos += 1;
+            }
+            if pos >= input.len() {
+                break;
+            }
+            let value = if bytes[pos] == b'"' {
+                let (value, end) = collect_http_quoted_string(input, pos, true);
+                pos = end + input[end..].find(';').unwrap_or(input.len() - end);
+                value
+            } else {
+                let value_len = input[pos..].find(';').unwrap_or(input.len() - pos);
+                #[allow(deprecated)]
+                let value = input[pos..pos + value_len].trim_right_matches(is_http_whitespace);
+                pos += value_len;
+                if value.is_empty() {
+                    continue;
+                }
+                value.to_string()
+            };
+            if !name.is_empty()
+                && name.chars().all(is_http_token)
+                && value.chars().all(is_http_quoted_string_token)
+                && mime_type.parameter(&name).is_none()
+            {
+                mime_type.parameters.push((name, value));
+            }
+        }
+        Some(mime_type)
+    }
+
+    /// The type, e.g. `text` for `text/html`.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn type_name(&self) -> &str {
+        &self.type_name
+    }
+
+    /// The subtype, e.g. `html` for `text/html`.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn subtype(&self) -> &str {
+        &self.subtype
+    }
+
+    /// The type and the subtype separated by a slash, e.g. `text/html`.
+    ///
+    /// Available to Rust only.
+    pub fn essence(&self) -> String {
+        format!("{}/{}", self.type_name, self.subtype)
+    }
+
+    /// The parameters as lowercase name and value pairs in the order they
+    /// appeared.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn parameters(&self) -> &[(String, String)] {
+        &self.parameters[..]
+    }
+
+    /// The value of the parameter whose name matches `name` ASCII
+    /// case-insensitively.
+    ///
+    /// Available to Rust only.
+    pub fn parameter(&self, name: &str) -> Option<&str> {
+        self.parameters
+            .iter()
+            .find(|parameter| parameter.0.eq_ignore_ascii_case(name))
+            .map(|parameter| &parameter.1[..])
+    }
+
+    /// The encoding named by the `charset` parameter or `None` if there is
+    /// no `charset` parameter or its value is not a known label.
+    ///
+    /// Available to Rust only.
+    pub fn charset(&self) -> Option<&'static Encoding> {
+        Encoding::for_label(self.parameter("charset")?.as_bytes())
+    }
+}
+
+/// Extracts the MIME type from the value of the `Content-Type` header
+/// following the Fetch Standard.
+///
+/// If the header occurs multiple times, `content_type` should be the values
+/// joined with `", "`. Values are split at commas outside quoted strings.
+/// The last value that parses and isn't `*/*` wins, but a `charset` from an
+/// earlier value with the same essence is carried over if the winning value
+/// lacks one.
+///
+/// Available to Rust only.
+pub fn extract_mime_type(content_type: &str) -> Option<MimeType> {
+    let mut charset: Option<String> = None;
+    let mut essence: Option<String> = None;
+    let mut mime_type = None;
+    for value in split_header_value(content_type) {
+        let mut candidate = match MimeType::parse(&value) {
+            Some(candidate) => candidate,
+            None => continue,
+        };
+        let candidate_essence = candidate.essence();
+        if candidate_essence == "*/*" {
+            continue;
+        }
+        if essence.as_ref() != Some(&candidate_essence) {
+            charset = candidate.parameter("charset").map(|c| c.to_string());
+            essence = Some(candidate_essence);
+        } else if candidate.parameter("charset").is_none() {
+            if let Some(ref charset) = charset {
+                candidate
+                    .parameters
+                    .push(("charset".to_string(), charset.clone()));
+            }
+        }
+        mime_type = Some(candidate);
+    }
+    mime_type
+}
+
+/// Determines the encoding from the value of the `Content-Type` header
+/// following the Fetch Standard's _legacy extract an encoding_, which is
+/// what browsers use for XHR responses, scripts and stylesheets.
+///
+/// Returns `fallback` if the header has no usable MIME type, the MIME type
+/// has no `charset` parameter or its value is not a known label.
+///
+/// Available to Rust only.
+pub fn legacy_extract_encoding(
+    content_type: &str,
+    fallback: &'static Encoding,
+) -> &'static Encoding {
+    match extract_mime_type(content_type).and_then(|mime_type| mime_type.charset()) {
+        Some(encoding) => encoding,
+        None => fallback,
+    }
+}
+
+#[inline(always)]
+fn is_http_whitespace(c: char) -> bool {
+    c == '\n' || c == '\r' || c == '\t' || c == ' '
+}
+
+fn is_http_token(c: char) -> bool {
+    c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c)
+}
+
+fn is_http_quoted_string_token(c: char) -> bool {
+    c == '\t' || (c >= ' ' && c != '\u{7F}' && c <= '\u{FF}')
+}
+
+/// Collects an HTTP quoted string starting at the quote at `start`. Returns
+/// the unquoted value if `extract_value` is true and the quoted string as is
+/// otherwise together with the position after the string.
+fn collect_http_quoted_string(input: &str, start: usize, extract_value: bool) -> (String, usize) {
+    let mut value = String::new();
+    let mut pos = start + 1;
+    loop {
+        let len = input[pos..]
+            .find(&['"', '\\'][..])
+            .unwrap_or(input.len() - pos);
+        value.push_str(&input[pos..pos + len]);
+        pos += len;
+        if pos >= input.len() {
+            break;
+        }
+        let quote_or_backslash = input.as_bytes()[pos];
+        pos += 1;
+        if quote_or_backslash != b'\\' {
+            break;
+        }
+        match input[pos..].chars().next() {
+            Some(c) => {
+                value.push(c);
+                pos += c.len_utf8();
+            }
+            None => {
+                value.push('\\');
+                break;
+            }
+        }
+    }
+    if extract_value {
+        (value, pos)
+    } else {
+        (input[start..pos].to_string(), pos)
+    }
+}
+
+/// Splits a header value at commas that are not inside quoted strings.
+fn split_header_value(input: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    let mut value = String::new();
+    let mut pos = 0;
+    loop {
+        let len = input[pos..]
+            .find(&['"', ','][..])
+            .unwrap_or(input.len() - pos);
+        value.push_str(&input[pos..pos + len]);
+        pos += len;
+        if pos < input.len() && input.as_bytes()[pos] == b'"' {
+            let (quoted, end) = collect_http_quoted_string(input, pos, false);
+            value.push_str(&quoted);
+            pos = end;
+            if pos < input.len() {
+                continue;
+            }
+        }
+        values.push(value.trim_matches(|c| c == '\t' || c == ' ').to_string());
+        value.clear();
+        if pos >= input.len() {
+            return values;
+        }
+        pos += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    fn serialize(mime_type: &MimeType) -> String {
+        let mut s = mime_type.essence();
+        for parameter in mime_type.parameters() {
+            s.push(';');
+            s.push_str(&parameter.0);
+            s.push('=');
+            s.push_str(&parameter.1);
+        }
+        s
+    }
+
+    fn parsed(input: &str) -> Option<String> {
+        MimeType::parse(input).map(|mime_type| serialize(&mime_type))
+    }
+
+    fn extracted(input: &str) -> Option<String> {
+        extract_mime_type(input).map(|mime_type| serialize(&mime_type))
+    }
+
+    #[test]
+    fn test_mime_parse() {
+        let mime_type = MimeType::parse(" Text/HTML ; Charset=\"Shift_JIS\"\t").unwrap();
+        assert_eq!(mime_type.type_name(), "text");
+        assert_eq!(mime_type.subtype(), "html");
+        assert_eq!(mime_type.essence(), "text/html");
+        assert_eq!(mime_type.parameter("CHARSET"), Some("Shift_JIS"));
+        assert_eq!(mime_type.charset(), Some(SHIFT_JIS));
+        assert_eq!(parsed("text/html;charset=gbk"), Some("text/html;charset=gbk".to_string()));
+        assert_eq!(parsed("text/html ;charset=gbk"), Some("text/html;charset=gbk".to_string()));
+        assert_eq!(parsed("text/html; charset=gbk "), Some("text/html;charset=gbk".to_string()));
+        assert_eq!(parsed("text/html;;;charset=gbk"), Some("text/html;charset=gbk".to_string()));
+        assert_eq!(parsed("text/html;charset =gbk"), Some("text/html".to_string()));
+        assert_eq!(parsed("text/html;charset= gbk"), Some("text/html;charset= gbk".to_string()));
+    }
+
+    #[test]
+    fn test_mime_parse_quoting() {
+        assert_eq!(
+            parsed("text/html;charset=\"gb\\k\\\"\" ignored;a=b"),
+            Some("text/html;charset=gbk\";a=b".to_string())
+        );
+        assert_eq!(
+            parsed("text/html;charset=\"gbk;x=y\""),
+            Some("text/html;charset=gbk;x=y".to_string())
+        );
+        assert_eq!(parsed("text/html;charset=\"gbk"), Some("text/html;charset=gbk".to_string()));
+        assert_eq!(parsed("text/html;charset=\"\\"), Some("text/html;charset=\\".to_string()));
+        assert_eq!(parsed("text/html;charset=\"\""), Some("text/html;charset=".to_string()));
+    }
+
+    #[test]
+    fn test_mime_parse_parameter_rules() {
+        assert_eq!(
+            parsed("text/html;charset=gbk;CHARSET=big5"),
+            Some("text/html;charset=gbk".to_string())
+        );
+        assert_eq!(
+            parsed("text/html;charset=;charset=big5"),
+            Some("text/html;charset=big5".to_string())
+        );
+        assert_eq!(
+            parsed("text/html;char set=gbk;charset=big5"),
+            Some("text/html;charset=big5".to_string())
+        );
+        assert_eq!(
+            parsed("text/html;charset=\u{100};charset=big5"),
+            Some("text/html;charset=big5".to_string())
+        );
+        assert_eq!(parsed("text/html;charset"), Some("text/html".to_string()));
+        assert_eq!(parsed("text/html;charset="), Some("text/html".to_string()));
+    }
+
+    #[test]
+    fn test_mime_parse_failure() {
+        assert_eq!(parsed(""), None);
+        assert_eq!(parsed("text"), None);
+        assert_eq!(parsed("text/"), None);
+        assert_eq!(parsed("/html"), None);
+        assert_eq!(parsed("text /html"), None);
+        assert_eq!(parsed("te(x)t/html"), None);
+        assert_eq!(parsed("text/ html"), None);
+        assert_eq!(parsed("text/html;"), Some("text/html".to_string()));
+    }
+
+    #[test]
+    fn test_mime_extract() {
+        assert_eq!(extracted(""), None);
+        assert_eq!(extracted("bogus"), None);
+        assert_eq!(extracted("text/plain;charset=gbk, text/html"), Some("text/html".to_string()));
+        assert_eq!(
+            extracted("text/html;charset=gbk;a=b, text/html;x=y"),
+            Some("text/html;x=y;charset=gbk".to_string())
+        );
+        assert_eq!(
+            extracted("text/html;charset=gbk;a=b, text/html;x=y;charset=big5"),
+            Some("text/html;x=y;charset=big5".to_string())
+        );
+        assert_eq!(
+            extracted("text/html;charset=gbk, x/x, text/html;x=y"),
+            Some("text/html;x=y".to_string())
+        );
+        assert_eq!(extracted("text/html, cannot-parse"), Some("text/html".to_string()));
+        assert_eq!(extracted("text/html, */*"), Some("text/html".to_string()));
+        assert_eq!(extracted("text/html, "), Some("text/html".to_string()));
+        assert_eq!(
+            extracted("text/html;charset=\"gbk, big5\""),
+            Some("text/html;charset=gbk, big5".to_string())
+        );
+    }
+
+    #[test]
+    fn test_mime_legacy_extract_encoding() {
+        assert_eq!(legacy_extract_encoding("text/html;charset=\"gbk\"", UTF_8), GBK);
+        assert_eq!(legacy_extract_encoding("text/html;charset=bogus", UTF_8), UTF_8);
+        assert_eq!(legacy_extract_encoding("text/html", WINDOWS_1252), WINDOWS_1252);
+        assert_eq!(legacy_extract_encoding("", WINDOWS_1252), WINDOWS_1252);
+        assert_eq!(
+            legacy_extract_encoding("text/plain;charset=euc-kr, text/plain", UTF_8),
+            EUC_KR
+        );
+    }
+}
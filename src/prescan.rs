@@ -0,0 +1,343 @@
+// Copyright 2015-2016 Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The HTML `<meta>` prescan.
+//!
+//! This module implements the [_prescan a byte stream to determine its
+//! encoding_][1] algorithm from the HTML Standard, which browsers run on the
+//! first 1024 bytes of an HTML document when neither a BOM nor the transport
+//! layer has declared the encoding.
+//!
+//! [1]: https://html.spec.whatwg.org/multipage/parsing.html#prescan-a-byte-stream-to-determine-its-encoding
+
+use super::Encoding;
+use super::UTF_16BE;
+use super::UTF_16LE;
+use super::UTF_8;
+use super::WINDOWS_1252;
+use super::X_USER_DEFINED;
+
+/// The number of bytes from the start of the stream that the prescan
+/// examines.
+pub const PRESCAN_LENGTH: usize = 1024;
+
+/// Prescans the start of an HTML byte stream for a `<meta>` encoding
+/// declaration.
+///
+/// Bytes beyond the first [`PRESCAN_LENGTH`][1] are ignored. If the slice is
+/// shorter, it is treated as the whole stream, so a caller that is still
+/// waiting for network data should not give up on a `None` result until
+/// either 1024 bytes or the end of the stream are available.
+///
+/// As required by the HTML Standard, a declaration of UTF-16BE or UTF-16LE
+/// yields UTF-8 and a declaration of x-user-defined yields windows-1252.
+///
+/// Available to Rust only.
+///
+/// [1]: constant.PRESCAN_LENGTH.html
+pub fn prescan(bytes: &[u8]) -> Option<&'static Encoding> {
+    let bytes = if bytes.len() > PRESCAN_LENGTH {
+        &bytes[..PRESCAN_LENGTH]
+    } else {
+        bytes
+    };
+    let encoding = Prescanner { bytes, pos: 0 }.run()?;
+    if encoding == UTF_16BE || encoding == UTF_16LE {
+        Some(UTF_8)
+    } else if encoding == X_USER_DEFINED {
+        Some(WINDOWS_1252)
+    } else {
+        Some(encoding)
+    }
+}
+
+#[inline(always)]
+fn is_html_space(b: u8) -> bool {
+    b == b'\t' || b == b'\n' || b == 0x0C || b == b'\r' || b == b' '
+}
+
+fn starts_with_ignore_ascii_case(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.len() >= needle.len() && haystack[..needle.len()].eq_ignore_ascii_case(needle)
+}
+
+/// The attribute name and value as lowercased bytes.
+type Attribute = (Vec<u8>, Vec<u8>);
+
+struct Prescanner<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Prescanner<'a> {
+    /// The byte at the current position or `None` at the end of the input.
+    #[inline(always)]
+    fn byte(&self) -> Option<u8> {
+        self.bytes.get(self.pos).cloned()
+    }
+
+    #[inline(always)]
+    fn advance(&mut self) -> Option<u8> {
+        self.pos += 1;
+        self.byte()
+    }
+
+    /// Moves the position to the next `>` at or after `from`.
+    fn skip_to_gt(&mut self, from: usize) -> Option<()> {
+        let offset = self.bytes[from..].iter().position(|&b| b == b'>')?;
+        self.pos = from + offset;
+        Some(())
+    }
+
+    fn run(&mut self) -> Option<&'static Encoding> {
+        while self.pos < self.bytes.len() {
+            let tail = &self.bytes[self.pos..];
+            if tail.starts_with(b"<!--") {
+                // The `--` before the `>` may overlap with the `<!--`.
+                let start = self.pos + 2;
+                let offset = self.bytes[start..].windows(3).position(|w| w == b"-->")?;
+                self.pos = start + offset + 2;
+            } else if starts_with_ignore_ascii_case(tail, b"<meta")
+                && tail.len() > 5
+                && (is_html_space(tail[5]) || tail[5] == b'/')
+            {
+                self.pos += 5;
+                if let Some(encoding) = self.meta()? {
+                    return Some(encoding);
+                }
+            } else if tail.len() > 2
+                && tail[0] == b'<'
+                && (tail[1].is_ascii_alphabetic()
+                    || (tail[1] == b'/' && tail[2].is_ascii_alphabetic()))
+            {
+                let offset = tail.iter().position(|&b| is_html_space(b) || b == b'>')?;
+                self.pos += offset;
+                while self.attribute()?.is_some() {}
+            } else if tail.starts_with(b"<!") || tail.starts_with(b"</") || tail.starts_with(b"<?")
+            {
+                let from = self.pos + 1;
+                self.skip_to_gt(from)?;
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    /// Processes the attributes of a `<meta>` tag. The outer `None` means
+    /// that the input ended.
+    fn meta(&mut self) -> Option<Option<&'static Encoding>> {
+        let mut seen: Vec<Vec<u8>> = Vec::new();
+        let mut got_pragma = false;
+        let mut need_pragma = None;
+        // `None` is "null" and `Some(None)` is "failure" in spec terms.
+        let mut charset: Option<Option<&'static Encoding>> = None;
+        while let Some((name, value)) = self.attribute()? {
+            if seen.contains(&name) {
+                continue;
+            }
+            if name == b"http-equiv" {
+                if value == b"content-type" {
+                    got_pragma = true;
+                }
+            } else if name == b"content" {
+                if charset.is_none() {
+                    if let Some(encoding) = extract_from_content(&value) {
+                        charset = Some(Some(encoding));
+                        need_pragma = Some(true);
+                    }
+                }
+            } else if name == b"charset" {
+                charset = Some(Encoding::for_label(&value));
+                need_pragma = Some(false);
+            }
+            seen.push(name);
+        }
+        match need_pragma {
+            None => Some(None),
+            Some(true) if !got_pragma => Some(None),
+            _ => Some(charset.and_then(|c| c)),
+        }
+    }
+
+    /// Gets the next attribute of a tag. `Some(None)` means that the tag
+    /// ended and the outer `None` means that the input ended.
+    fn attribute(&mut self) -> Option<Option<Attribute>> {
+        let mut b = self.byte()?;
+        while is_html_space(b) || b == b'/' {
+            b = self.advance()?;
+        }
+        if b == b'>' {
+            return Some(None);
+        }
+        let mut name = Vec::new();
+        let mut value = Vec::new();
+        loop {
+            if b == b'=' && !name.is_empty() {
+                b = self.advance()?;
+                break;
+            }
+            if is_html_space(b) {
+                while is_html_space(b) {
+                    b = self.advance()?;
+                }
+                if b != b'=' {
+                    return Some(Some((name, value)));
+                }
+                b = self.advance()?;
+                break;
+            }
+            if b == b'/' || b == b'>' {
+                return Some(Some((name, value)));
+            }
+            name.push(b.to_ascii_lowercase());
+            b = self.advance()?;
+        }
+        while is_html_space(b) {
+            b = self.advance()?;
+        }
+        if b == b'"' || b == b'\'' {
+            let quote = b;
+            loop {
+                b = self.advance()?;
+                if b == quote {
+                    self.pos += 1;
+                    return Some(Some((name, value)));
+                }
+                value.push(b.to_ascii_lowercase());
+            }
+        }
+        loop {
+            if is_html_space(b) || b == b'>' {
+                return Some(Some((name, value)));
+            }
+            value.push(b.to_ascii_lowercase());
+            b = self.advance()?;
+        }
+    }
+}
+
+/// Implements the _extracting a character encoding from a `meta` element_
+/// algorithm on the value of a `content` attribute.
+fn extract_from_content(content: &[u8]) -> Option<&'static Encoding> {
+    let mut pos = 0;
+    loop {
+        let offset = content[pos..]
+            .windows(7)
+            .position(|w| w.eq_ignore_ascii_case(b"charset"))?;
+        pos += offset + 7;
+        while pos < content.len() && is_html_space(content[pos]) {
+            pos += 1;
+        }
+        if content.get(pos) != Some(&b'=') {
+            continue;
+        }
+        pos += 1;
+        while pos < content.len() && is_html_space(content[pos]) {
+            pos += 1;
+        }
+        let rest = &content[pos..];
+        let first = *rest.first()?;
+        if first == b'"' || first == b'\'' {
+            let len = rest[1..].iter().position(|&b| b == first)?;
+            return Encoding::for_label(&rest[1..1 + len]);
+        }
+        let len = rest
+            .iter()
+            .position(|&b| is_html_space(b) || b == b';')
+            .unwrap_or(rest.len());
+        return Encoding::for_label(&rest[..len]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    #[test]
+    fn test_prescan_meta_charset() {
+        assert_eq!(prescan(b"<meta charset=shift_jis>"), Some(SHIFT_JIS));
+        assert_eq!(prescan(b"<META CHARSET='EUC-JP'>"), Some(EUC_JP));
+        assert_eq!(prescan(b"<meta charset = \" koi8-r \">"), Some(KOI8_R));
+        assert_eq!(prescan(b"<meta/charset=gbk >"), Some(GBK));
+        assert_eq!(prescan(b"<!doctype html><html><head><meta charset=big5>"), Some(BIG5));
+        assert_eq!(prescan(b"<metacharset=big5>"), None);
+        assert_eq!(prescan(b""), None);
+    }
+
+    #[test]
+    fn test_prescan_http_equiv() {
+        assert_eq!(
+            prescan(b"<meta http-equiv=Content-Type content=\"text/html; charset=iso-8859-2\">"),
+            Some(ISO_8859_2)
+        );
+        assert_eq!(
+            prescan(b"<meta content='text/html;charset=\"windows-1251\"' http-equiv=content-type>"),
+            Some(WINDOWS_1251)
+        );
+        assert_eq!(prescan(b"<meta content=\"text/html; charset=iso-8859-2\">"), None);
+        assert_eq!(
+            prescan(b"<meta http-equiv=refresh content=\"text/html; charset=iso-8859-2\">"),
+            None
+        );
+        assert_eq!(
+            prescan(b"<meta http-equiv=content-type content=\"charsetx; charset = gbk;\">"),
+            Some(GBK)
+        );
+        assert_eq!(
+            prescan(b"<meta http-equiv=content-type content=\"charset='gbk\">"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_prescan_attribute_rules() {
+        // The first occurrence of an attribute wins.
+        assert_eq!(prescan(b"<meta charset=euc-jp charset=shift_jis>"), Some(EUC_JP));
+        // A charset attribute beats an earlier content attribute.
+        assert_eq!(
+            prescan(b"<meta content=\"text/html; charset=gbk\" charset=big5>"),
+            Some(BIG5)
+        );
+        // An unknown label does not stop the prescan.
+        assert_eq!(prescan(b"<meta charset=bogus><meta charset=utf-8>"), Some(UTF_8));
+        // Attribute values of other tags are skipped.
+        assert_eq!(
+            prescan(b"<div title=\"<meta charset=shift_jis>\"><meta charset=euc-kr>"),
+            Some(EUC_KR)
+        );
+    }
+
+    #[test]
+    fn test_prescan_comments_and_markup() {
+        assert_eq!(prescan(b"<!-- <meta charset=gbk> --><meta charset=big5>"), Some(BIG5));
+        assert_eq!(prescan(b"<!--><meta charset=big5>"), Some(BIG5));
+        assert_eq!(prescan(b"<!---><meta charset=gbk>--><meta charset=big5>"), Some(GBK));
+        assert_eq!(prescan(b"<!-- <meta charset=gbk>"), None);
+        assert_eq!(prescan(b"<?php <meta charset=gbk> ?><meta charset=big5>"), Some(BIG5));
+        assert_eq!(prescan(b"</p <meta charset=gbk>><meta charset=big5>"), Some(BIG5));
+    }
+
+    #[test]
+    fn test_prescan_overrides() {
+        assert_eq!(prescan(b"<meta charset=utf-16>"), Some(UTF_8));
+        assert_eq!(prescan(b"<meta charset=utf-16be>"), Some(UTF_8));
+        assert_eq!(prescan(b"<meta charset=x-user-defined>"), Some(WINDOWS_1252));
+    }
+
+    #[test]
+    fn test_prescan_length_limit() {
+        let mut bytes = vec![b' '; PRESCAN_LENGTH - 20];
+        bytes.extend_from_slice(b"<meta charset=gbk>");
+        assert_eq!(prescan(&bytes[..]), Some(GBK));
+        bytes.insert(0, b' ');
+        bytes.insert(0, b' ');
+        bytes.insert(0, b' ');
+        assert_eq!(prescan(&bytes[..]), None);
+    }
+}
@@ -188,6 +188,40 @@ impl ShiftJisEncoder {
         byte_length.checked_add(1)
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        if c <= '\u{7F}' {
+            return true;
+        }
+        if c > '\u{FFFF}' {
+            return false;
+        }
+        let bmp = c as u16;
+        if in_inclusive_range16(bmp, 0x3041, 0x3093) {
+            true
+        } else if in_inclusive_range16(bmp, 0x4E00, 0x9FA0) {
+            jis0208_level1_kanji_shift_jis_encode(bmp).is_some()
+                || 0x4EDD == bmp
+                || jis0208_level2_and_additional_kanji_encode(bmp).is_some()
+                || position(&IBM_KANJI[..], bmp).is_some()
+        } else if in_inclusive_range16(bmp, 0x30A1, 0x30F6)
+            || in_inclusive_range16(bmp, 0x3000, 0x3002)
+            || in_inclusive_range16(bmp, 0xFF61, 0xFF9F)
+            || in_inclusive_range16(bmp, 0x2170, 0x2179)
+            || in_inclusive_range16(bmp, 0xFA0E, 0xFA2D)
+        {
+            true
+        } else {
+            bmp == 0xA5
+                || bmp == 0x80
+                || bmp == 0x203E
+                || bmp == 0x2212
+                || bmp == 0xF929
+                || bmp == 0xF9DC
+                || jis0208_range_encode(bmp).is_some()
+                || jis0208_symbol_encode(bmp).is_some()
+        }
+    }
+
     ascii_compatible_bmp_encoder_functions!(
         {
             // Lunde says 60% Hiragana, 30% Kanji, 10% Katakana
@@ -254,6 +254,16 @@ impl SingleByteEncoder {
         Some(byte_length)
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        if c <= '\u{7F}' {
+            return true;
+        }
+        if c > '\u{FFFF}' {
+            return false;
+        }
+        self.encode_u16(c as u16).is_some()
+    }
+
     fn encode_u16(&self, code_unit: u16) -> Option<u8> {
         // We search the quadrants in reverse order, but we search forward
         // within each quadrant. For Windows and ISO encodings, this is
@@ -626,6 +626,10 @@ impl Utf8Encoder {
         Some(byte_length)
     }
 
+    pub fn is_mappable(&self, _c: char) -> bool {
+        true
+    }
+
     #[cfg_attr(feature = "cargo-clippy", allow(never_loop))]
     pub fn encode_from_utf16_raw(
         &mut self,
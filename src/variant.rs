@@ -249,6 +249,20 @@ impl VariantEncoder {
         }
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        match *self {
+            VariantEncoder::SingleByte(ref v) => v.is_mappable(c),
+            VariantEncoder::Utf8(ref v) => v.is_mappable(c),
+            VariantEncoder::Gb18030(ref v) => v.is_mappable(c),
+            VariantEncoder::Big5(ref v) => v.is_mappable(c),
+            VariantEncoder::EucJp(ref v) => v.is_mappable(c),
+            VariantEncoder::Iso2022Jp(ref v) => v.is_mappable(c),
+            VariantEncoder::ShiftJis(ref v) => v.is_mappable(c),
+            VariantEncoder::EucKr(ref v) => v.is_mappable(c),
+            VariantEncoder::UserDefined(ref v) => v.is_mappable(c),
+        }
+    }
+
     pub fn encode_from_utf16_raw(
         &mut self,
         src: &[u16],
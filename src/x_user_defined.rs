@@ -10,6 +10,8 @@
 use super::*;
 use handles::*;
 use variant::*;
+// Rust 1.14.0 requires the following despite the asterisk above.
+use super::in_inclusive_range32;
 
 cfg_if!{
     if #[cfg(feature = "simd-accel")] {
@@ -167,6 +169,10 @@ impl UserDefinedEncoder {
         Some(byte_length)
     }
 
+    pub fn is_mappable(&self, c: char) -> bool {
+        c <= '\u{7F}' || in_inclusive_range32(c as u32, 0xF780, 0xF7FF)
+    }
+
     encoder_functions!(
         {},
         {
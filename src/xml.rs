@@ -0,0 +1,374 @@
+// Copyright 2015-2016 Mozilla Foundation. See the COPYRIGHT
+// file at the top-level directory of this distribution.
+//
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// https://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or https://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Encoding detection for XML documents.
+//!
+//! This module implements the autodetection described in [Appendix F][1] of
+//! XML 1.0 restricted to the encodings of the Encoding Standard: a BOM is
+//! honored first, then the byte pattern of a UTF-16 `<?xml` without a BOM
+//! and then the `encoding` pseudo-attribute of the XML declaration, whose
+//! value is resolved using [`Encoding::for_label()`][2]. Encodings that the
+//! Encoding Standard does not support, such as UCS-4 and EBCDIC, are not
+//! detected.
+//!
+//! [1]: https://www.w3.org/TR/xml/#sec-guessing
+//! [2]: ../struct.Encoding.html#method.for_label
+
+use super::Encoding;
+use super::UTF_16BE;
+use super::UTF_16LE;
+use super::UTF_8;
+
+/// The maximum number of bytes examined when looking for the end of the XML
+/// declaration.
+const DECLARATION_LIMIT: usize = 1024;
+
+/// What determined the encoding of an XML document.
+///
+/// Available to Rust only.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmlEncodingSource {
+    /// A byte order mark.
+    Bom,
+    /// The `<?xml` of a document without a BOM was in UTF-16.
+    Utf16Pattern,
+    /// The `encoding` pseudo-attribute of the XML declaration.
+    Declaration,
+    /// Nothing; XML defaults to UTF-8.
+    Default,
+}
+
+/// The result of [`sniff()`][1].
+///
+/// Available to Rust only.
+///
+/// [1]: fn.sniff.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct XmlSniffResult {
+    encoding: &'static Encoding,
+    source: XmlEncodingSource,
+    bom_length: usize,
+    declared_encoding: Option<&'static Encoding>,
+    conflict: bool,
+}
+
+impl XmlSniffResult {
+    /// The encoding to decode the document with.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn encoding(&self) -> &'static Encoding {
+        self.encoding
+    }
+
+    /// What determined the encoding.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn source(&self) -> XmlEncodingSource {
+        self.source
+    }
+
+    /// The length of the BOM, if any, at the start of the input.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn bom_length(&self) -> usize {
+        self.bom_length
+    }
+
+    /// The encoding named by the `encoding` pseudo-attribute of the XML
+    /// declaration or `None` if there was no declaration, no `encoding`
+    /// pseudo-attribute or the label was not recognized.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn declared_encoding(&self) -> Option<&'static Encoding> {
+        self.declared_encoding
+    }
+
+    /// Whether the XML declaration disagrees with the BOM or with the byte
+    /// pattern of the declaration itself, for example a UTF-16 BOM followed by
+    /// `encoding="Shift_JIS"` or an ASCII-compatible declaration claiming
+    /// UTF-16. In the case of a conflict, the BOM or byte pattern wins.
+    ///
+    /// Available to Rust only.
+    #[inline]
+    pub fn has_conflict(&self) -> bool {
+        self.conflict
+    }
+}
+
+/// Determines the encoding of an XML document from its first bytes.
+///
+/// The input should contain the whole XML declaration if the document has
+/// one; bytes past the first 1024 bytes following the BOM are not examined.
+///
+/// Available to Rust only.
+pub fn sniff(bytes: &[u8]) -> XmlSniffResult {
+    if let Some((encoding, bom_length)) = Encoding::for_bom(bytes) {
+        let declared_encoding = declared_encoding(&bytes[bom_length..], encoding);
+        let conflict = match declared_encoding {
+            Some(declared) => !same_family(declared, encoding),
+            None => false,
+        };
+        return XmlSniffResult {
+            encoding,
+            source: XmlEncodingSource::Bom,
+            bom_length,
+            declared_encoding,
+            conflict,
+        };
+    }
+    let pattern = if bytes.starts_with(b"\x00<\x00?") {
+        Some(UTF_16BE)
+    } else if bytes.starts_with(b"<\x00?\x00") {
+        Some(UTF_16LE)
+    } else {
+        None
+    };
+    if let Some(encoding) = pattern {
+        let declared_encoding = declared_encoding(bytes, encoding);
+        let conflict = match declared_encoding {
+            Some(declared) => !same_family(declared, encoding),
+            None => false,
+        };
+        return XmlSniffResult {
+            encoding,
+            source: XmlEncodingSource::Utf16Pattern,
+            bom_length: 0,
+            declared_encoding,
+            conflict,
+        };
+    }
+    match declared_encoding(bytes, UTF_8) {
+        Some(declared) if declared == UTF_16BE || declared == UTF_16LE => XmlSniffResult {
+            encoding: UTF_8,
+            source: XmlEncodingSource::Declaration,
+            bom_length: 0,
+            declared_encoding: Some(declared),
+            conflict: true,
+        },
+        Some(declared) => XmlSniffResult {
+            encoding: declared,
+            source: XmlEncodingSource::Declaration,
+            bom_length: 0,
+            declared_encoding: Some(declared),
+            conflict: false,
+        },
+        None => XmlSniffResult {
+            encoding: UTF_8,
+            source: XmlEncodingSource::Default,
+            bom_length: 0,
+            declared_encoding: None,
+            conflict: false,
+        },
+    }
+}
+
+/// Whether a declaration of `declared` is consistent with a document
+/// detected as `detected`. The label "UTF-16" resolves to UTF-16LE, so either
+/// UTF-16 variant is accepted in a UTF-16 document.
+fn same_family(declared: &'static Encoding, detected: &'static Encoding) -> bool {
+    if detected == UTF_16BE || detected == UTF_16LE {
+        declared == UTF_16BE || declared == UTF_16LE
+    } else {
+        declared == detected
+    }
+}
+
+#[inline(always)]
+fn is_xml_space(b: u8) -> bool {
+    b == b' ' || b == b'\t' || b == b'\r' || b == b'\n'
+}
+
+/// Resolves the `encoding` pseudo-attribute of an XML declaration at the
+/// start of `bytes`, which are in `encoding`.
+fn declared_encoding(bytes: &[u8], encoding: &'static Encoding) -> Option<&'static Encoding> {
+    let bytes = if bytes.len() > DECLARATION_LIMIT {
+        &bytes[..DECLARATION_LIMIT]
+    } else {
+        bytes
+    };
+    if encoding == UTF_16BE || encoding == UTF_16LE {
+        // The declaration is ASCII-only, so narrowing it is lossless up to
+        // the first non-ASCII code unit.
+        let narrowed: Vec<u8> = bytes
+            .chunks(2)
+            .map(|pair| {
+                if pair.len() < 2 {
+                    return 0x80;
+                }
+                let (high, low) = if encoding == UTF_16BE {
+                    (pair[0], pair[1])
+                } else {
+                    (pair[1], pair[0])
+                };
+                if high == 0 && low < 0x80 {
+                    low
+                } else {
+                    0x80
+                }
+            })
+            .take_while(|&b| b < 0x80)
+            .collect();
+        return Encoding::for_label(declared_label(&narrowed)?);
+    }
+    Encoding::for_label(declared_label(bytes)?)
+}
+
+/// Extracts the value of the `encoding` pseudo-attribute from an ASCII XML
+/// declaration at the start of `decl`.
+fn declared_label(decl: &[u8]) -> Option<&[u8]> {
+    if !decl.starts_with(b"<?xml") {
+        return None;
+    }
+    let mut pos = 5;
+    loop {
+        let space_start = pos;
+        while pos < decl.len() && is_xml_space(decl[pos]) {
+            pos += 1;
+        }
+        if pos == space_start || decl[pos..].starts_with(b"?") {
+            return None;
+        }
+        let name_start = pos;
+        while pos < decl.len() && decl[pos].is_ascii_alphabetic() {
+            pos += 1;
+        }
+        let name = &decl[name_start..pos];
+        if name.is_empty() {
+            return None;
+        }
+        while pos < decl.len() && is_xml_space(decl[pos]) {
+            pos += 1;
+        }
+        if decl.get(pos) != Some(&b'=') {
+            return None;
+        }
+        pos += 1;
+        while pos < decl.len() && is_xml_space(decl[pos]) {
+            pos += 1;
+        }
+        let quote = *decl.get(pos)?;
+        if quote != b'"' && quote != b'\'' {
+            return None;
+        }
+        pos += 1;
+        let len = decl[pos..]
+            .iter()
+            .position(|&b| b == quote || b == b'<' || b == b'>')?;
+        if decl[pos + len] != quote {
+            return None;
+        }
+        if name == b"encoding" {
+            return Some(&decl[pos..pos + len]);
+        }
+        pos += len + 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::*;
+    use super::*;
+
+    fn utf16(string: &str, big_endian: bool) -> Vec<u8> {
+        let mut vec = Vec::new();
+        for unit in string.encode_utf16() {
+            if big_endian {
+                vec.push((unit >> 8) as u8);
+                vec.push(unit as u8);
+            } else {
+                vec.push(unit as u8);
+                vec.push((unit >> 8) as u8);
+            }
+        }
+        vec
+    }
+
+    #[test]
+    fn test_xml_declaration() {
+        let result = sniff(b"<?xml version=\"1.0\" encoding=\"Shift_JIS\"?><a/>");
+        assert_eq!(result.encoding(), SHIFT_JIS);
+        assert_eq!(result.source(), XmlEncodingSource::Declaration);
+        assert_eq!(result.declared_encoding(), Some(SHIFT_JIS));
+        assert!(!result.has_conflict());
+        assert_eq!(
+            sniff(b"<?xml version='1.0'\n  encoding = 'euc-kr' standalone='yes'?>").encoding(),
+            EUC_KR
+        );
+        assert_eq!(sniff(b"<?xml encoding=\"iso-8859-2\"?>").encoding(), ISO_8859_2);
+    }
+
+    #[test]
+    fn test_xml_default() {
+        let result = sniff(b"<rss version=\"2.0\"/>");
+        assert_eq!(result.encoding(), UTF_8);
+        assert_eq!(result.source(), XmlEncodingSource::Default);
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?>").source(), XmlEncodingSource::Default);
+        assert_eq!(sniff(b"<?xml encoding=\"bogus\"?>").source(), XmlEncodingSource::Default);
+        assert_eq!(sniff(b"<?xmlencoding=\"gbk\"?>").source(), XmlEncodingSource::Default);
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?><a encoding='gbk'/>").encoding(), UTF_8);
+        assert_eq!(sniff(b"<?xml encoding=\"gbk").encoding(), UTF_8);
+        assert_eq!(sniff(b"").encoding(), UTF_8);
+    }
+
+    #[test]
+    fn test_xml_bom() {
+        let result = sniff(b"\xEF\xBB\xBF<?xml version=\"1.0\"?>");
+        assert_eq!(result.encoding(), UTF_8);
+        assert_eq!(result.source(), XmlEncodingSource::Bom);
+        assert_eq!(result.bom_length(), 3);
+        assert!(!result.has_conflict());
+
+        let mut bytes = vec![0xFEu8, 0xFF];
+        bytes.extend_from_slice(&utf16("<?xml version=\"1.0\" encoding=\"UTF-16\"?>", true));
+        let result = sniff(&bytes[..]);
+        assert_eq!(result.encoding(), UTF_16BE);
+        assert_eq!(result.bom_length(), 2);
+        assert_eq!(result.declared_encoding(), Some(UTF_16LE));
+        assert!(!result.has_conflict());
+    }
+
+    #[test]
+    fn test_xml_conflict() {
+        let mut bytes = vec![0xFFu8, 0xFE];
+        bytes.extend_from_slice(&utf16("<?xml version=\"1.0\" encoding=\"Shift_JIS\"?>", false));
+        let result = sniff(&bytes[..]);
+        assert_eq!(result.encoding(), UTF_16LE);
+        assert_eq!(result.declared_encoding(), Some(SHIFT_JIS));
+        assert!(result.has_conflict());
+
+        let result = sniff(b"\xEF\xBB\xBF<?xml version=\"1.0\" encoding=\"windows-1252\"?>");
+        assert_eq!(result.encoding(), UTF_8);
+        assert!(result.has_conflict());
+
+        let result = sniff(b"<?xml version=\"1.0\" encoding=\"UTF-16\"?>");
+        assert_eq!(result.encoding(), UTF_8);
+        assert_eq!(result.source(), XmlEncodingSource::Declaration);
+        assert!(result.has_conflict());
+    }
+
+    #[test]
+    fn test_xml_utf16_pattern() {
+        let bytes = utf16("<?xml version=\"1.0\" encoding=\"utf-16\"?>", true);
+        let result = sniff(&bytes[..]);
+        assert_eq!(result.encoding(), UTF_16BE);
+        assert_eq!(result.source(), XmlEncodingSource::Utf16Pattern);
+        assert_eq!(result.bom_length(), 0);
+        assert!(!result.has_conflict());
+
+        let bytes = utf16("<?xml version=\"1.0\" encoding=\"gbk\"?>", false);
+        let result = sniff(&bytes[..]);
+        assert_eq!(result.encoding(), UTF_16LE);
+        assert_eq!(result.declared_encoding(), Some(GBK));
+        assert!(result.has_conflict());
+    }
+}